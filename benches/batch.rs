@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use gxhash::*;
+use rand::Rng;
+
+const COUNT: usize = 10000;
+
+fn batch_benchmark(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let keys: Vec<u64> = (0..COUNT).map(|_| rng.gen()).collect();
+    let mut bytes = vec![0u8; COUNT * 16];
+    rng.fill(bytes.as_mut_slice());
+    let inputs: Vec<&[u8]> = bytes.chunks_exact(16).collect();
+    let mut out = vec![0u64; COUNT];
+
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(COUNT as u64));
+
+    group.bench_function("u64 keys one by one", |b| b.iter(|| {
+        for (key, out) in keys.iter().zip(out.iter_mut()) {
+            *out = gxhash64(&key.to_le_bytes(), 0);
+        }
+        black_box(&out);
+    }));
+    group.bench_function("u64 keys batched", |b| b.iter(|| {
+        gxhash64_batch_u64(&keys, 0, &mut out);
+        black_box(&out);
+    }));
+    group.bench_function("16 bytes inputs one by one", |b| b.iter(|| {
+        for (input, out) in inputs.iter().zip(out.iter_mut()) {
+            *out = gxhash64(input, 0);
+        }
+        black_box(&out);
+    }));
+    group.bench_function("16 bytes inputs batched", |b| b.iter(|| {
+        gxhash64_batch(&inputs, 0, &mut out);
+        black_box(&out);
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, batch_benchmark);
+criterion_main!(benches);
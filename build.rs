@@ -2,6 +2,17 @@ extern crate rustc_version;
 use rustc_version::{version_meta, Channel};
 
 fn main() {
+    println!("cargo:rustc-check-cfg=cfg(hybrid)");
+
+    // Page size assumed by reads beyond the input, 4KiB by default (see src/gxhash/page.rs)
+    println!("cargo:rerun-if-env-changed=GXHASH_PAGE_SIZE");
+    if let Ok(page_size) = std::env::var("GXHASH_PAGE_SIZE") {
+        match page_size.parse::<usize>() {
+            Ok(size) if size.is_power_of_two() && size >= 64 => println!("cargo:rustc-env=GXHASH_PAGE_SIZE={size}"),
+            _ => panic!("GXHASH_PAGE_SIZE must be a power of two of at least 64 bytes, got '{page_size}'"),
+        }
+    }
+
     // When conditions permits, enable hybrid feature to leverage wider intrinsics for even more throughput
     if version_meta().unwrap().channel == Channel::Nightly
     && cfg!(target_arch = "x86_64")
@@ -9,11 +20,4 @@ fn main() {
     && cfg!(target_feature = "vaes") {
         println!("cargo:rustc-cfg=hybrid");
     }
-
-    // If not cross compiling, make sure the aes feature is available
-    if std::env::var("HOST").unwrap_or_default() == std::env::var("TARGET").unwrap_or_default()
-    && cfg!(not(target_feature = "aes")) {
-        panic!("| GxHash requires target-feature 'aes' to be enabled.\n\
-        | Build with RUSTFLAGS=\"-C target-cpu=native\" or RUSTFLAGS=\"-C target-feature=+aes\" to enable.");
-    }
 }
\ No newline at end of file
@@ -0,0 +1,82 @@
+//! Generates the known-answer test vectors of the current version of the algorithm, checked by the tests.
+//!
+//! ```bash
+//! cargo run --example generate_vectors > vectors/v3.txt
+//! ```
+
+use std::hash::Hasher;
+
+use gxhash::v3::*;
+
+const SEEDS: [i64; 4] = [0, 42, -1, i64::MIN];
+const MAX_LEN: usize = 1024;
+const MAX_WRITE_LEN: usize = 128;
+
+fn main() {
+    let input: Vec<u8> = (0..MAX_LEN).map(|i| (i * 31 + 7) as u8).collect();
+
+    print!(
+        "\
+# GxHash v3 known-answer test vectors, generated by `cargo run --example generate_vectors > vectors/v3.txt`
+#
+# Inputs are the first <len> bytes of the sequence b[i] = (i * 31 + 7) mod 256.
+# Seeds and values are decimal integers. Hashes are lowercase hexadecimal integers, zero-padded to their width.
+#
+# gxhash <seed> <len> <gxhash32> <gxhash64> <gxhash128>
+#   Hashes of the input by the gxhash32, gxhash64 and gxhash128 functions.
+# write <seed> <len> <finish> <finish_u128>
+#   Hashes of GxHasher::with_seed(seed) after a single write of the input.
+# write_<type> <seed> <value> <finish> <finish_u128>
+#   Hashes of GxHasher::with_seed(seed) after a single write_<type>(value). Pointer-sized writes are omitted.
+"
+    );
+
+    for seed in SEEDS {
+        for len in 0..=MAX_LEN {
+            let input = &input[..len];
+            println!(
+                "gxhash {seed} {len} {:08x} {:016x} {:032x}",
+                gxhash32(input, seed),
+                gxhash64(input, seed),
+                gxhash128(input, seed)
+            );
+        }
+    }
+
+    for seed in SEEDS {
+        for len in 0..=MAX_WRITE_LEN {
+            let mut hasher = GxHasher::with_seed(seed);
+            hasher.write(&input[..len]);
+            println!("write {seed} {len} {:016x} {:032x}", hasher.finish(), hasher.finish_u128());
+        }
+    }
+
+    macro_rules! write {
+        ($name:ident, $type:ident, [$($value:expr),*]) => {
+            for seed in SEEDS {
+                for value in [$($value),*] {
+                    let mut hasher = GxHasher::with_seed(seed);
+                    hasher.$name(value);
+                    println!("{} {seed} {value} {:016x} {:032x}", stringify!($name), hasher.finish(), hasher.finish_u128());
+                }
+            }
+        };
+        (unsigned $name:ident, $type:ident) => {
+            write!($name, $type, [0, 1, 42, $type::MAX / 3, $type::MAX])
+        };
+        (signed $name:ident, $type:ident) => {
+            write!($name, $type, [0, 1, -1, 42, $type::MIN, $type::MAX])
+        };
+    }
+
+    write!(unsigned write_u8, u8);
+    write!(unsigned write_u16, u16);
+    write!(unsigned write_u32, u32);
+    write!(unsigned write_u64, u64);
+    write!(unsigned write_u128, u128);
+    write!(signed write_i8, i8);
+    write!(signed write_i16, i16);
+    write!(signed write_i32, i32);
+    write!(signed write_i64, i64);
+    write!(signed write_i128, i128);
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn main() {
+    let version = std::env::var("CARGO_PKG_VERSION").unwrap();
+    // As with Cargo, versions are compatible as long as their leftmost non-zero component is the same
+    let soversion = match version.split('.').collect::<Vec<_>>()[..] {
+        ["0", minor, ..] => format!("0.{minor}"),
+        [major, ..] => major.to_string(),
+        _ => unreachable!(),
+    };
+
+    // The native libraries are also needed by the tests linking C programs to the static library
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+    let private_libs = native_static_libs(out_dir);
+    println!("cargo:rustc-env=GXHASH_NATIVE_STATIC_LIBS={}", private_libs.join(" "));
+
+    // Shared library the soname points to. Packaging is only provided for Linux and macOS.
+    let shared_library = match std::env::var("CARGO_CFG_TARGET_OS").unwrap().as_str() {
+        "linux" => {
+            println!("cargo:rustc-cdylib-link-arg=-Wl,-soname,libgxhash.so.{soversion}");
+            format!("libgxhash.so.{soversion}")
+        }
+        "macos" => {
+            println!("cargo:rustc-cdylib-link-arg=-Wl,-install_name,@rpath/libgxhash.{soversion}.dylib");
+            format!("libgxhash.{soversion}.dylib")
+        }
+        _ => return,
+    };
+
+    // The pkg-config and CMake files are generated in OUT_DIR, where install.sh looks for them
+    for file in ["gxhash.pc", "gxhashConfig.cmake", "gxhashConfigVersion.cmake"] {
+        let template = Path::new("packaging").join(format!("{file}.in"));
+        println!("cargo:rerun-if-changed={}", template.display());
+        let contents = std::fs::read_to_string(&template)
+            .unwrap()
+            .replace("@VERSION@", &version)
+            .replace("@SOVERSION_REGEX@", &soversion.replace('.', "\\\\."))
+            .replace("@SHARED_LIBRARY@", &shared_library)
+            .replace("@PRIVATE_LIBS@", &private_libs.join(" "))
+            .replace(
+                "@PRIVATE_LIBS_CMAKE@",
+                &private_libs.iter().map(|lib| lib.trim_start_matches("-l")).collect::<Vec<_>>().join(";"),
+            );
+        std::fs::write(out_dir.join(file), contents).unwrap();
+    }
+}
+
+// Native libraries the static library needs on the target, as listed by `rustc --print native-static-libs` for an
+// empty static library, since the library itself only depends on the standard library. Frameworks are kept with
+// their name, as a single argument.
+fn native_static_libs(out_dir: &Path) -> Vec<String> {
+    let probe = out_dir.join("libprobe.a");
+    // The empty crate is read from the standard input
+    let output = Command::new(std::env::var("RUSTC").unwrap())
+        .args(["--crate-type=staticlib", "--crate-name=probe", "--print=native-static-libs", "--target"])
+        .arg(std::env::var("TARGET").unwrap())
+        .arg("-o")
+        .arg(&probe)
+        .arg("-")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(probe);
+
+    // Printed as a note on the standard error, or on the standard output by newer compilers
+    let printed = String::from_utf8_lossy(&output.stderr) + String::from_utf8_lossy(&output.stdout);
+    let libs = printed
+        .lines()
+        .find_map(|line| line.split_once("native-static-libs: "))
+        .unwrap_or_else(|| panic!("rustc didn't print the native libraries of the target: {printed}"))
+        .1;
+    let mut args = libs.split_whitespace();
+    let mut private_libs = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg {
+            "-framework" => private_libs.push(format!("-framework {}", args.next().unwrap())),
+            _ => private_libs.push(arg.to_string()),
+        }
+    }
+    private_libs
+}
@@ -0,0 +1,12 @@
+#include <inttypes.h>
+#include <stdio.h>
+#include <string.h>
+
+#include <gxhash.h>
+
+/* Prints the 64-bit hash of the first argument */
+int main(int argc, char** argv) {
+    const char* input = argc > 1 ? argv[1] : "";
+    printf("%016" PRIx64 "\n", gxhash64(input, strlen(input), 0));
+    return 0;
+}
@@ -0,0 +1,82 @@
+/*
+ * C bindings of GxHash, implemented by the gxhash_ffi crate.
+ *
+ * Link against the static (libgxhash.a) or dynamic (libgxhash.so, libgxhash.dylib, gxhash.dll) library built by
+ * `cargo build --release` in the ffi directory. This header is kept in sync with ffi/src/lib.rs by hand, which a
+ * test of the crate checks.
+ */
+
+#ifndef GXHASH_H
+#define GXHASH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Inputs are given as a pointer and a length. A NULL pointer is the empty input when the length is 0, as with
+ * gxhash64(NULL, 0, seed), and any pointer can be given for a length of 0. A NULL pointer with a non-zero length,
+ * or a NULL hasher, aborts the process with a message on the standard error: no returned hash could be told apart
+ * from a valid one.
+ */
+
+/* 128-bit hash, as its low and high 64 bits */
+typedef struct gxhash128_t {
+    uint64_t low;
+    uint64_t high;
+} gxhash128_t;
+
+/* Hashes len bytes at buf using the provided seed */
+uint32_t gxhash32(const void* buf, size_t len, int64_t seed);
+uint64_t gxhash64(const void* buf, size_t len, int64_t seed);
+gxhash128_t gxhash128(const void* buf, size_t len, int64_t seed);
+
+/*
+ * Hashes n inputs, the i-th one being lens[i] bytes at bufs[i], writing their gxhash64 hashes to out. Hashing many
+ * small inputs at once is faster than hashing them one by one. The arrays may be NULL when n is 0.
+ */
+void gxhash64_many(const void* const* bufs, const size_t* lens, size_t n, int64_t seed, uint64_t* out);
+
+/*
+ * Hashes n records of record_len bytes, the i-th one starting i * stride bytes after base, such as a field of an
+ * array of structs, writing their gxhash64 hashes to out.
+ */
+void gxhash64_strided(const void* base, size_t stride, size_t record_len, size_t n, int64_t seed, uint64_t* out);
+
+/*
+ * Opaque hasher, for hashing an input written in several pieces. The hash depends on how the input is split into
+ * writes, so it is not the same as the one of gxhash64 or gxhash128 on the whole input.
+ */
+typedef struct gxhash_state gxhash_state;
+
+/* Creates a hasher with an empty seed, or with the provided seed, to be released with gxhash_state_free */
+gxhash_state* gxhash_state_new(void);
+gxhash_state* gxhash_state_with_seed(int64_t seed);
+
+/* Writes len bytes at buf into the hasher */
+void gxhash_state_write(gxhash_state* state, const void* buf, size_t len);
+
+/* Hash of the bytes written so far. The hasher can still be written to afterwards. */
+uint64_t gxhash_state_finish64(const gxhash_state* state);
+gxhash128_t gxhash_state_finish128(const gxhash_state* state);
+
+/* Releases a hasher. Releasing NULL does nothing. */
+void gxhash_state_free(gxhash_state* state);
+
+/*
+ * Name of the implementation hashes are computed with, such as "x86-aes", "x86-vaes", "arm-aes" or "portable",
+ * as a static string. Hashes are the same whatever the implementation.
+ */
+const char* gxhash_backend(void);
+
+/* Version of the library, such as "0.1.0", as a static string */
+const char* gxhash_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GXHASH_H */
@@ -0,0 +1,44 @@
+#!/bin/sh
+# Installs the C library built by `cargo build --release`, with its header, pkg-config file and CMake package,
+# under a prefix:
+#   ./install.sh /usr/local
+# The profile of the build to install can be given after the prefix (release by default).
+set -eu
+
+prefix=${1:?usage: install.sh PREFIX [PROFILE]}
+profile=${2:-release}
+ffi_dir=$(cd "$(dirname "$0")" && pwd)
+build_dir=${CARGO_TARGET_DIR:-$ffi_dir/target}/$profile
+# The pkg-config and CMake files are generated by build.rs in its OUT_DIR, the latest one for the profile
+out_dir=$(ls -dt "$build_dir"/build/gxhash_ffi-*/out 2>/dev/null | head -n 1)
+if [ ! -f "$out_dir/gxhash.pc" ]; then
+    echo "install.sh: no pkg-config file found in $build_dir/build, build the library first" >&2
+    exit 1
+fi
+
+version=$(sed -n 's/^Version: //p' "$out_dir/gxhash.pc")
+# As in build.rs, versions are compatible as long as their leftmost non-zero component is the same
+case $version in
+    0.*) soversion=${version%.*} ;;
+    *) soversion=${version%%.*} ;;
+esac
+
+mkdir -p "$prefix/include" "$prefix/lib/pkgconfig" "$prefix/lib/cmake/gxhash"
+cp "$ffi_dir/include/gxhash.h" "$prefix/include/"
+cp "$build_dir/libgxhash.a" "$prefix/lib/"
+cp "$out_dir/gxhash.pc" "$prefix/lib/pkgconfig/"
+cp "$out_dir/gxhashConfig.cmake" "$out_dir/gxhashConfigVersion.cmake" "$prefix/lib/cmake/gxhash/"
+
+cd "$prefix/lib"
+case $(uname) in
+    Darwin)
+        cp "$build_dir/libgxhash.dylib" "libgxhash.$version.dylib"
+        ln -sf "libgxhash.$version.dylib" "libgxhash.$soversion.dylib"
+        ln -sf "libgxhash.$soversion.dylib" libgxhash.dylib
+        ;;
+    *)
+        cp "$build_dir/libgxhash.so" "libgxhash.so.$version"
+        ln -sf "libgxhash.so.$version" "libgxhash.so.$soversion"
+        ln -sf "libgxhash.so.$soversion" libgxhash.so
+        ;;
+esac
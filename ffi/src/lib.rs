@@ -1,13 +1,341 @@
+//! C bindings of GxHash, declared by include/gxhash.h.
+//!
+//! Inputs are given as a pointer and a length. A NULL pointer is the empty input when the length is 0, as with
+//! `gxhash64(NULL, 0, seed)`, and any pointer can be given for a length of 0. A NULL pointer with a non-zero length,
+//! or a NULL hasher, is a bug of the caller which the process is aborted on, with a message on the standard error:
+//! no returned hash could be told apart from a valid one.
+
+use core::ffi::c_char;
+use core::hash::Hasher;
 use core::slice;
+use std::ffi::CString;
+use std::sync::OnceLock;
+
+use gxhash::GxHasher;
+
+/// 128-bit hash, split in two halves so that it can be returned by value through the C ABI
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gxhash128 {
+    pub low: u64,
+    pub high: u64,
+}
+
+impl From<u128> for Gxhash128 {
+    #[inline]
+    fn from(hash: u128) -> Gxhash128 {
+        Gxhash128 {
+            low: hash as u64,
+            high: (hash >> 64) as u64,
+        }
+    }
+}
+
+/// Opaque handle on a [`GxHasher`], for hashing an input written in several pieces
+pub struct GxhashState(GxHasher);
+
+#[cold]
+fn abort(message: &str) -> ! {
+    eprintln!("gxhash: {message}");
+    std::process::abort()
+}
+
+// Unlike slice::from_raw_parts, accepts NULL or dangling pointers for empty inputs
+#[inline]
+unsafe fn input<'a>(buf: *const (), len: usize) -> &'a [u8] {
+    if len == 0 {
+        return &[];
+    }
+    if buf.is_null() {
+        abort("NULL input with a non-zero length");
+    }
+    slice::from_raw_parts(buf as *const u8, len)
+}
+
+// Same as input, for the arrays of the batch functions
+#[inline]
+unsafe fn array<'a, T>(ptr: *const T, len: usize) -> &'a [T] {
+    if len == 0 {
+        return &[];
+    }
+    if ptr.is_null() {
+        abort("NULL array with a non-zero length");
+    }
+    slice::from_raw_parts(ptr, len)
+}
+
+#[inline]
+unsafe fn array_mut<'a, T>(ptr: *mut T, len: usize) -> &'a mut [T] {
+    if len == 0 {
+        return &mut [];
+    }
+    if ptr.is_null() {
+        abort("NULL array with a non-zero length");
+    }
+    slice::from_raw_parts_mut(ptr, len)
+}
+
+// Number of inputs handed at once to gxhash64_batch by the batch functions, which gather their slices on the stack
+const BATCH: usize = 64;
+
+// Hashes n inputs, given by their index, with gxhash64_batch
+#[inline]
+unsafe fn batch<'a>(n: usize, seed: i64, out: *mut u64, input: impl Fn(usize) -> &'a [u8]) {
+    let out = array_mut(out, n);
+    let mut inputs: [&[u8]; BATCH] = [&[]; BATCH];
+    for (chunk, out) in out.chunks_mut(BATCH).enumerate() {
+        for (i, slot) in inputs[..out.len()].iter_mut().enumerate() {
+            *slot = input(chunk * BATCH + i);
+        }
+        gxhash::gxhash64_batch(&inputs[..out.len()], seed, out);
+    }
+}
 
+#[inline]
+unsafe fn hasher<'a>(state: *const GxhashState) -> &'a GxHasher {
+    match state.as_ref() {
+        Some(state) => &state.0,
+        None => abort("NULL hasher"),
+    }
+}
+
+#[inline]
+unsafe fn hasher_mut<'a>(state: *mut GxhashState) -> &'a mut GxHasher {
+    match state.as_mut() {
+        Some(state) => &mut state.0,
+        None => abort("NULL hasher"),
+    }
+}
+
+/// Hashes `len` bytes at `buf` using the provided seed
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes, or be NULL if `len` is 0.
 #[no_mangle]
 pub unsafe extern "C" fn gxhash32(buf: *const (), len: usize, seed: i64) -> u32 {
-    let data: &[u8] = slice::from_raw_parts(buf as *const u8, len);
+    let data = input(buf, len);
     gxhash::gxhash32(data, seed)
 }
 
+/// Hashes `len` bytes at `buf` using the provided seed
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes, or be NULL if `len` is 0.
 #[no_mangle]
 pub unsafe extern "C" fn gxhash64(buf: *const (), len: usize, seed: i64) -> u64 {
-    let data: &[u8] = slice::from_raw_parts(buf as *const u8, len);
+    let data = input(buf, len);
     gxhash::gxhash64(data, seed)
-}
\ No newline at end of file
+}
+
+/// Hashes `len` bytes at `buf` using the provided seed
+///
+/// # Safety
+/// `buf` must point to `len` readable bytes, or be NULL if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash128(buf: *const (), len: usize, seed: i64) -> Gxhash128 {
+    let data = input(buf, len);
+    gxhash::gxhash128(data, seed).into()
+}
+
+/// Hashes `n` inputs, the i-th one being `lens[i]` bytes at `bufs[i]`, writing their hashes to `out`. Hashes are
+/// the same as the ones of [`gxhash64`], but hashing many small inputs at once is faster.
+///
+/// # Safety
+/// `bufs`, `lens` and `out` must point to `n` elements, or be NULL if `n` is 0, and each input must follow the
+/// contract of [`gxhash64`]. `out` must not overlap the inputs.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash64_many(bufs: *const *const (), lens: *const usize, n: usize, seed: i64, out: *mut u64) {
+    let (bufs, lens) = (array(bufs, n), array(lens, n));
+    batch(n, seed, out, |i| input(bufs[i], lens[i]));
+}
+
+/// Hashes `n` records of `record_len` bytes, the i-th one starting `i * stride` bytes after `base`, such as a field
+/// of an array of structs, writing their hashes to `out`. Hashes are the same as the ones of [`gxhash64`].
+///
+/// # Safety
+/// The records must be readable, `base` can only be NULL if `n` or `record_len` is 0, and `out` must point to `n`
+/// elements, or be NULL if `n` is 0. `out` must not overlap the records.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash64_strided(base: *const (), stride: usize, record_len: usize, n: usize, seed: i64, out: *mut u64) {
+    let base = base as *const u8;
+    batch(n, seed, out, |i| input(base.wrapping_add(i * stride) as *const (), record_len));
+}
+
+/// Creates a hasher with an empty seed, to be released with [`gxhash_state_free`]
+#[no_mangle]
+pub extern "C" fn gxhash_state_new() -> *mut GxhashState {
+    Box::into_raw(Box::new(GxhashState(GxHasher::default())))
+}
+
+/// Creates a hasher using the provided seed, to be released with [`gxhash_state_free`]
+#[no_mangle]
+pub extern "C" fn gxhash_state_with_seed(seed: i64) -> *mut GxhashState {
+    Box::into_raw(Box::new(GxhashState(GxHasher::with_seed(seed))))
+}
+
+/// Writes bytes into the hasher. As with the [`Hasher`] it wraps, the hash depends on how the input is split
+/// into writes.
+///
+/// # Safety
+/// `state` must be a live hasher and `buf` must point to `len` readable bytes, or be NULL if `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash_state_write(state: *mut GxhashState, buf: *const (), len: usize) {
+    let data = input(buf, len);
+    hasher_mut(state).write(data);
+}
+
+/// 64-bit hash of the bytes written so far. The hasher can still be written to afterwards.
+///
+/// # Safety
+/// `state` must be a live hasher.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash_state_finish64(state: *const GxhashState) -> u64 {
+    hasher(state).finish()
+}
+
+/// 128-bit hash of the bytes written so far. The hasher can still be written to afterwards.
+///
+/// # Safety
+/// `state` must be a live hasher.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash_state_finish128(state: *const GxhashState) -> Gxhash128 {
+    hasher(state).finish_u128().into()
+}
+
+/// Releases a hasher created by [`gxhash_state_new`] or [`gxhash_state_with_seed`]. Releasing NULL does nothing.
+///
+/// # Safety
+/// `state` must be NULL or a live hasher, which can't be used anymore once released.
+#[no_mangle]
+pub unsafe extern "C" fn gxhash_state_free(state: *mut GxhashState) {
+    if !state.is_null() {
+        drop(Box::from_raw(state));
+    }
+}
+
+/// Name of the implementation hashes are computed with, as returned by [`gxhash::backend`], such as "x86-aes" or
+/// "portable", as a static NUL-terminated string
+#[no_mangle]
+pub extern "C" fn gxhash_backend() -> *const c_char {
+    static BACKEND: OnceLock<CString> = OnceLock::new();
+    BACKEND.get_or_init(|| CString::new(gxhash::backend()).unwrap()).as_ptr()
+}
+
+/// Version of the bindings, such as "0.1.0", as a static NUL-terminated string
+#[no_mangle]
+pub extern "C" fn gxhash_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn hash128_halves() {
+        let input = b"hello world";
+        let hash = unsafe { gxhash128(input.as_ptr() as *const (), input.len(), 42) };
+        let expected = gxhash::gxhash128(input, 42);
+        assert_eq!(expected as u64, hash.low);
+        assert_eq!((expected >> 64) as u64, hash.high);
+        assert_eq!(hash.low, unsafe { gxhash64(input.as_ptr() as *const (), input.len(), 42) });
+    }
+
+    #[test]
+    fn state_matches_hasher() {
+        let mut hasher = GxHasher::with_seed(42);
+        hasher.write(b"hello");
+        hasher.write(b"world");
+
+        unsafe {
+            let state = gxhash_state_with_seed(42);
+            gxhash_state_write(state, b"hello".as_ptr() as *const (), 5);
+            gxhash_state_write(state, b"world".as_ptr() as *const (), 5);
+            assert_eq!(hasher.finish(), gxhash_state_finish64(state));
+            assert_eq!(Gxhash128::from(hasher.finish_u128()), gxhash_state_finish128(state));
+            gxhash_state_free(state);
+
+            let state = gxhash_state_new();
+            assert_eq!(GxHasher::default().finish(), gxhash_state_finish64(state));
+            gxhash_state_free(state);
+            gxhash_state_free(core::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn empty_inputs_accept_any_pointer() {
+        let empty: &[u8] = &[];
+        let null = core::ptr::null();
+        let dangling = core::ptr::NonNull::<u64>::dangling().as_ptr() as *const ();
+        for seed in [0, 42] {
+            unsafe {
+                assert_eq!(gxhash::gxhash32(empty, seed), gxhash32(null, 0, seed));
+                assert_eq!(gxhash::gxhash64(empty, seed), gxhash64(null, 0, seed));
+                assert_eq!(Gxhash128::from(gxhash::gxhash128(empty, seed)), gxhash128(null, 0, seed));
+                assert_eq!(gxhash::gxhash64(empty, seed), gxhash64(dangling, 0, seed));
+            }
+        }
+
+        let mut hasher = GxHasher::with_seed(42);
+        hasher.write(empty);
+        unsafe {
+            let state = gxhash_state_with_seed(42);
+            gxhash_state_write(state, null, 0);
+            assert_eq!(hasher.finish(), gxhash_state_finish64(state));
+            gxhash_state_free(state);
+        }
+    }
+
+    #[test]
+    fn many_matches_gxhash64() {
+        let input: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        // More inputs than a batch, of lengths going through all the paths of gxhash64
+        let inputs: Vec<&[u8]> = (0..200).map(|i| &input[i..i + (i * 7) % 300]).collect();
+        let bufs: Vec<*const ()> = inputs.iter().map(|input| input.as_ptr() as *const ()).collect();
+        let lens: Vec<usize> = inputs.iter().map(|input| input.len()).collect();
+
+        for n in [0, 1, 63, 64, 65, 200] {
+            let mut out = vec![0u64; n];
+            unsafe { gxhash64_many(bufs.as_ptr(), lens.as_ptr(), n, 42, out.as_mut_ptr()) };
+            for (input, hash) in inputs.iter().zip(&out) {
+                assert_eq!(gxhash::gxhash64(input, 42), *hash);
+            }
+        }
+
+        // Empty inputs can be NULL, as can the arrays when there are no inputs
+        let mut out = [1u64; 2];
+        unsafe {
+            gxhash64_many([core::ptr::null(); 2].as_ptr(), [0; 2].as_ptr(), 2, 42, out.as_mut_ptr());
+            gxhash64_many(core::ptr::null(), core::ptr::null(), 0, 42, core::ptr::null_mut());
+        }
+        assert_eq!([gxhash::gxhash64(&[], 42); 2], out);
+    }
+
+    #[test]
+    fn strided_matches_gxhash64() {
+        let records: Vec<u8> = (0..5000).map(|i| (i * 31) as u8).collect();
+        for (stride, record_len) in [(24, 8), (24, 24), (40, 33), (1, 16), (0, 5), (16, 0)] {
+            let n = 100;
+            let mut out = vec![0u64; n];
+            unsafe { gxhash64_strided(records.as_ptr() as *const (), stride, record_len, n, 7, out.as_mut_ptr()) };
+            for (i, hash) in out.iter().enumerate() {
+                assert_eq!(gxhash::gxhash64(&records[i * stride..i * stride + record_len], 7), *hash);
+            }
+        }
+
+        let mut out = [0u64; 3];
+        unsafe { gxhash64_strided(core::ptr::null(), 8, 0, 3, 7, out.as_mut_ptr()) };
+        assert_eq!([gxhash::gxhash64(&[], 7); 3], out);
+    }
+
+    #[test]
+    fn backend_and_version_are_c_strings() {
+        let backend = unsafe { core::ffi::CStr::from_ptr(gxhash_backend()) };
+        assert_eq!(gxhash::backend(), backend.to_str().unwrap());
+        assert_eq!(gxhash_backend(), gxhash_backend());
+
+        let version = unsafe { core::ffi::CStr::from_ptr(gxhash_version()) };
+        assert_eq!(env!("CARGO_PKG_VERSION"), version.to_str().unwrap());
+    }
+}
@@ -0,0 +1,97 @@
+/*
+ * Hashes inputs through the C ABI and prints the hashes, for tests/c_abi.rs to compare them with the ones of the
+ * gxhash crate. Exits with a non-zero status if the bindings are inconsistent with each other.
+ * With an argument, misuses the bindings in the way it names instead, which must abort.
+ */
+
+#include <inttypes.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "gxhash.h"
+
+#define CHECK(condition)                                                    \
+    if (!(condition)) {                                                     \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+        return 1;                                                           \
+    }
+
+int main(int argc, char** argv) {
+    if (argc > 1) {
+        if (strcmp(argv[1], "null-input") == 0) {
+            gxhash64(NULL, 1, 0);
+        } else if (strcmp(argv[1], "null-state") == 0) {
+            gxhash_state_write(NULL, "hello", 5);
+        }
+        return 0;
+    }
+
+    uint8_t input[300];
+    for (size_t i = 0; i < sizeof(input); i++) {
+        input[i] = (uint8_t)(i % 251);
+    }
+
+    const int64_t seeds[] = {0, 42, -1, INT64_MIN};
+    for (size_t s = 0; s < sizeof(seeds) / sizeof(seeds[0]); s++) {
+        for (size_t len = 0; len <= sizeof(input); len++) {
+            uint32_t hash32 = gxhash32(input, len, seeds[s]);
+            uint64_t hash64 = gxhash64(input, len, seeds[s]);
+            gxhash128_t hash128 = gxhash128(input, len, seeds[s]);
+            CHECK(hash64 == hash128.low);
+            CHECK(hash32 == (uint32_t)hash128.low);
+            if (len == 0) {
+                CHECK(hash64 == gxhash64(NULL, 0, seeds[s]));
+            }
+            printf("hash %zu %" PRId64 " %016" PRIx64 "%016" PRIx64 "\n", len, seeds[s], hash128.high, hash128.low);
+        }
+    }
+
+    /* Batches, checked against gxhash64 */
+    const void* bufs[100];
+    size_t lens[100];
+    uint64_t hashes[100];
+    for (size_t i = 0; i < 100; i++) {
+        bufs[i] = input + i;
+        lens[i] = (i * 7) % 200;
+    }
+    gxhash64_many(bufs, lens, 100, 42, hashes);
+    for (size_t i = 0; i < 100; i++) {
+        CHECK(hashes[i] == gxhash64(bufs[i], lens[i], 42));
+    }
+
+    struct record {
+        uint32_t id;
+        uint8_t key[12];
+        double value;
+    } records[100];
+    memset(records, 0, sizeof(records));
+    for (size_t i = 0; i < 100; i++) {
+        records[i].id = (uint32_t)i;
+        memcpy(records[i].key, input + i, sizeof(records[i].key));
+    }
+    gxhash64_strided(records[0].key, sizeof(struct record), sizeof(records[0].key), 100, 42, hashes);
+    for (size_t i = 0; i < 100; i++) {
+        CHECK(hashes[i] == gxhash64(records[i].key, sizeof(records[i].key), 42));
+    }
+
+    gxhash_state* state = gxhash_state_with_seed(42);
+    CHECK(state != NULL);
+    gxhash_state_write(state, "hello", 5);
+    gxhash_state_write(state, "world", 5);
+    uint64_t finish64 = gxhash_state_finish64(state);
+    gxhash128_t finish128 = gxhash_state_finish128(state);
+    CHECK(finish64 == finish128.low);
+    CHECK(finish64 == gxhash_state_finish64(state));
+    printf("state %016" PRIx64 "%016" PRIx64 "\n", finish128.high, finish128.low);
+    gxhash_state_free(state);
+
+    state = gxhash_state_new();
+    printf("empty %016" PRIx64 "\n", gxhash_state_finish64(state));
+    gxhash_state_free(state);
+    gxhash_state_free(NULL);
+
+    printf("backend %s\n", gxhash_backend());
+    printf("version %s\n", gxhash_version());
+
+    return 0;
+}
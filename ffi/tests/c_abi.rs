@@ -0,0 +1,98 @@
+//! Builds the static library, compiles tests/c/abi.c against it and gxhash.h, runs it, and checks the hashes it
+//! prints against the ones of the gxhash crate. The C compiler is the one of the CC environment variable, or cc.
+
+#![cfg(unix)]
+
+use std::hash::Hasher;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use gxhash::GxHasher;
+
+mod common;
+
+// Same on every unix
+const SIGABRT: i32 = 6;
+
+// Path of the compiled C program, compiled once for all tests
+fn c_program() -> &'static Path {
+    static EXECUTABLE: OnceLock<PathBuf> = OnceLock::new();
+    EXECUTABLE.get_or_init(compile)
+}
+
+fn compile() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = common::build_library();
+    let executable = target_dir.join("c_abi");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let mut command = Command::new(compiler);
+    command
+        .arg(manifest_dir.join("tests/c/abi.c"))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest_dir.join("include"))
+        .arg(target_dir.join("libgxhash.a"))
+        .arg("-o")
+        .arg(&executable);
+    // Native libraries the Rust standard library links to, as listed by `--print native-static-libs` in build.rs
+    command.args(env!("GXHASH_NATIVE_STATIC_LIBS").split_whitespace());
+    let status = command.status().expect("failed to run the C compiler");
+    assert!(status.success(), "failed to compile abi.c");
+    executable
+}
+
+#[test]
+fn c_program_gets_same_hashes() {
+    let output = Command::new(c_program()).output().unwrap();
+    assert!(output.status.success(), "abi.c failed: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    let mut expected = String::new();
+    let input: Vec<u8> = (0..300).map(|i| (i % 251) as u8).collect();
+    for seed in [0, 42, -1, i64::MIN] {
+        for len in 0..=input.len() {
+            expected += &format!("hash {len} {seed} {:032x}\n", gxhash::gxhash128(&input[..len], seed));
+        }
+    }
+
+    let mut hasher = GxHasher::with_seed(42);
+    hasher.write(b"hello");
+    hasher.write(b"world");
+    expected += &format!("state {:032x}\n", hasher.finish_u128());
+    expected += &format!("empty {:016x}\n", GxHasher::default().finish());
+    expected += &format!("backend {}\n", gxhash::backend());
+    expected += &format!("version {}\n", env!("CARGO_PKG_VERSION"));
+
+    assert_eq!(expected, stdout);
+}
+
+#[test]
+fn misuses_abort() {
+    for misuse in ["null-input", "null-state"] {
+        let output = Command::new(c_program()).arg(misuse).output().unwrap();
+        assert_eq!(Some(SIGABRT), output.status.signal(), "{misuse} didn't abort");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("NULL"));
+    }
+}
+
+#[test]
+fn header_declares_every_function() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let header = std::fs::read_to_string(manifest_dir.join("include/gxhash.h")).unwrap();
+    let source = std::fs::read_to_string(manifest_dir.join("src/lib.rs")).unwrap();
+
+    let functions: Vec<&str> = source
+        .split("extern \"C\" fn ")
+        .skip(1)
+        .map(|declaration| declaration.split('(').next().unwrap())
+        .collect();
+    assert!(!functions.is_empty());
+    for function in functions {
+        assert!(header.contains(&format!(" {function}(")), "{function} is not declared in gxhash.h");
+    }
+}
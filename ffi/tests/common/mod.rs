@@ -0,0 +1,21 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+// `cargo test` doesn't build the libraries, so they are built in a target directory of their own, next to the one
+// of the test executables, to not wait on the lock of the running build. Returns the directory of the libraries.
+pub fn build_library() -> PathBuf {
+    let target_dir = std::env::current_exe().unwrap().parent().unwrap().parent().unwrap().join("c-library");
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "build",
+            "--lib",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+            "--target-dir",
+        ])
+        .arg(&target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to build the library");
+    target_dir.join("debug")
+}
@@ -0,0 +1,143 @@
+//! Installs the libraries under a prefix with install.sh, and builds examples/cmake against them with pkg-config,
+//! and with CMake when it is available.
+
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+mod common;
+
+fn available(tool: &str) -> bool {
+    Command::new(tool).arg("--version").output().is_ok()
+}
+
+fn soversion() -> String {
+    match env!("CARGO_PKG_VERSION").split('.').collect::<Vec<_>>()[..] {
+        ["0", minor, ..] => format!("0.{minor}"),
+        [major, ..] => major.to_string(),
+        _ => unreachable!(),
+    }
+}
+
+// Runs an example built against the installed libraries, and checks the hash it prints
+fn check_example(executable: &Path, prefix: &Path) {
+    let output = Command::new(executable)
+        .arg("hello world")
+        .env("LD_LIBRARY_PATH", prefix.join("lib"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{} failed: {}",
+        executable.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        format!("{:016x}\n", gxhash::gxhash64(b"hello world", 0)),
+        String::from_utf8(output.stdout).unwrap()
+    );
+}
+
+// Installs the libraries once for all tests, returning the prefix
+fn install() -> PathBuf {
+    static PREFIX: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+    PREFIX
+        .get_or_init(|| {
+            let target_dir = common::build_library().parent().unwrap().to_path_buf();
+            let prefix = target_dir.join("prefix");
+            let _ = fs::remove_dir_all(&prefix);
+            let status = Command::new(concat!(env!("CARGO_MANIFEST_DIR"), "/install.sh"))
+                .arg(&prefix)
+                .arg("debug")
+                .env("CARGO_TARGET_DIR", &target_dir)
+                .status()
+                .unwrap();
+            assert!(status.success(), "install.sh failed");
+            prefix
+        })
+        .clone()
+}
+
+#[test]
+fn installs_libraries_and_packages() {
+    let prefix = install();
+    let version = env!("CARGO_PKG_VERSION");
+    for file in [
+        "include/gxhash.h".to_string(),
+        "lib/libgxhash.a".to_string(),
+        "lib/libgxhash.so".to_string(),
+        format!("lib/libgxhash.so.{}", soversion()),
+        format!("lib/libgxhash.so.{version}"),
+        "lib/pkgconfig/gxhash.pc".to_string(),
+        "lib/cmake/gxhash/gxhashConfig.cmake".to_string(),
+        "lib/cmake/gxhash/gxhashConfigVersion.cmake".to_string(),
+    ] {
+        assert!(prefix.join(&file).exists(), "{file} is not installed");
+    }
+}
+
+#[test]
+fn links_with_pkg_config() {
+    if !available("pkg-config") {
+        eprintln!("pkg-config is not available, skipping");
+        return;
+    }
+    let prefix = install();
+    let pkg_config = |args: &[&str]| {
+        let output = Command::new("pkg-config")
+            .args(args)
+            .arg("gxhash")
+            .env("PKG_CONFIG_PATH", prefix.join("lib/pkgconfig"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "pkg-config failed: {}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(env!("CARGO_PKG_VERSION"), pkg_config(&["--modversion"]).trim());
+
+    let build_dir = prefix.parent().unwrap().join("pkg-config-build");
+    fs::create_dir_all(&build_dir).unwrap();
+    let executable = build_dir.join("example");
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/cmake/main.c"))
+        .args(pkg_config(&["--cflags", "--libs"]).split_whitespace())
+        .arg("-o")
+        .arg(&executable)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile the example");
+
+    // The example must depend on the soname, rather than on the unversioned name it was linked with
+    if let Ok(readelf) = Command::new("readelf").arg("-d").arg(&executable).output() {
+        let dynamic = String::from_utf8(readelf.stdout).unwrap();
+        assert!(dynamic.contains(&format!("[libgxhash.so.{}]", soversion())), "{dynamic}");
+    }
+    check_example(&executable, &prefix);
+}
+
+#[test]
+fn links_with_cmake() {
+    if !available("cmake") {
+        eprintln!("cmake is not available, skipping");
+        return;
+    }
+    let prefix = install();
+    let build_dir = prefix.parent().unwrap().join("cmake-build");
+    let _ = fs::remove_dir_all(&build_dir);
+
+    let status = Command::new("cmake")
+        .args(["-S", concat!(env!("CARGO_MANIFEST_DIR"), "/examples/cmake"), "-B"])
+        .arg(&build_dir)
+        .arg(format!("-DCMAKE_PREFIX_PATH={}", prefix.display()))
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to configure the CMake example");
+    let status = Command::new("cmake").arg("--build").arg(&build_dir).status().unwrap();
+    assert!(status.success(), "failed to build the CMake example");
+
+    check_example(&build_dir.join("example"), &prefix);
+    check_example(&build_dir.join("example_static"), &prefix);
+}
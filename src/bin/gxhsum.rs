@@ -0,0 +1,519 @@
+//! Prints or checks GxHash checksums of files, in the same format as `sha256sum` and other coreutils tools.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use gxhash::{gxhash128_reader, gxhash128_reader_with_len};
+
+const USAGE: &str = "\
+Usage: gxhsum [OPTION]... [FILE]...
+Print or check GxHash checksums.
+
+With no FILE, or when FILE is -, read standard input.
+
+  -b, --bits BITS   size of the checksums: 32, 64 or 128 (default)
+  -s, --seed SEED   seed of the checksums, a signed 64-bit integer (default 0)
+  -r, --recursive   hash the files of directories and their subdirectories, in the order of their names
+  -c, --check       read checksums from the FILEs and check them, whatever their size
+  -q, --quiet       when checking, don't print OK for each successfully verified file
+  -h, --help        display this help and exit
+
+Exit status is 0 on success, 1 if a checksum did not match, and 2 if a file could not be read or the usage
+is invalid.
+";
+
+// Exit codes, the most severe one being returned when several things go wrong
+const SUCCESS: u8 = 0;
+const MISMATCH: u8 = 1;
+const ERROR: u8 = 2;
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    bits: u32,
+    seed: i64,
+    recursive: bool,
+    check: bool,
+    quiet: bool,
+    files: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = run(&args, &mut io::stdin().lock(), &mut io::stdout().lock(), &mut io::stderr().lock());
+    ExitCode::from(code)
+}
+
+fn run(args: &[String], stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> u8 {
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            let _ = out.write_all(USAGE.as_bytes());
+            return SUCCESS;
+        }
+        Err(message) => {
+            let _ = writeln!(err, "gxhsum: {message}\nTry 'gxhsum --help' for more information.");
+            return ERROR;
+        }
+    };
+
+    let mut code = SUCCESS;
+    for file in &options.files {
+        let result = if options.check {
+            check(file, &options, stdin, out, err)
+        } else {
+            print(Path::new(file), &options, stdin, out, err)
+        };
+        code = code.max(result);
+    }
+    code
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        bits: 128,
+        seed: 0,
+        recursive: false,
+        check: false,
+        quiet: false,
+        files: Vec::new(),
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        // Options taking a value accept it in the same argument after a '=', or as the next argument
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or(format!("option '{name}' requires a value"))
+        };
+
+        match name {
+            "-b" | "--bits" => {
+                options.bits = match value(name)?.as_str() {
+                    "32" => 32,
+                    "64" => 64,
+                    "128" => 128,
+                    bits => return Err(format!("invalid number of bits '{bits}', expected 32, 64 or 128")),
+                }
+            }
+            "-s" | "--seed" => {
+                let seed = value(name)?;
+                options.seed = seed.parse().map_err(|_| format!("invalid seed '{seed}'"))?;
+            }
+            "-r" | "--recursive" => options.recursive = true,
+            "-c" | "--check" => options.check = true,
+            "-q" | "--quiet" => options.quiet = true,
+            "-h" | "--help" => return Ok(None),
+            "--" => options.files.extend(args.by_ref().cloned()),
+            "-" => options.files.push(arg.clone()),
+            _ if name.starts_with('-') => return Err(format!("unrecognized option '{arg}'")),
+            _ => options.files.push(arg.clone()),
+        }
+    }
+
+    if options.files.is_empty() {
+        options.files.push("-".to_string());
+    }
+    Ok(Some(options))
+}
+
+/// Prints the checksums of a file, or of the files of a directory when recursive
+fn print(path: &Path, options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> u8 {
+    if options.recursive && path.is_dir() {
+        let entries = match sorted_entries(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let _ = writeln!(err, "gxhsum: {}: {e}", path.display());
+                return ERROR;
+            }
+        };
+        return entries.iter().map(|entry| print(entry, options, stdin, out, err)).max().unwrap_or(SUCCESS);
+    }
+
+    match hash(path, options.seed, stdin) {
+        Ok(hash) => {
+            let _ = writeln!(out, "{}", format_line(hash, options.bits, &path.to_string_lossy()));
+            SUCCESS
+        }
+        Err(e) => {
+            let _ = writeln!(err, "gxhsum: {}: {e}", path.display());
+            ERROR
+        }
+    }
+}
+
+/// Entries of a directory, sorted by name, so that checksums are always listed in the same order.
+/// Symbolic links to directories are not followed, to avoid cycles.
+fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_symlink() && entry.path().is_dir() {
+            continue;
+        }
+        entries.push(entry.path());
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// 128-bit hash of a file, or of the standard input for '-'. Narrower hashes are its lowest bits.
+fn hash(path: &Path, seed: i64, stdin: &mut dyn Read) -> io::Result<u128> {
+    if path == Path::new("-") {
+        return gxhash128_reader(stdin, seed);
+    }
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    if metadata.is_dir() {
+        return Err(io::Error::other("Is a directory"));
+    }
+    // Pipes, devices and procfs files report no length, so they are buffered as they are read, as are files
+    // larger than the address space of 32-bit targets
+    let len = match usize::try_from(metadata.len()) {
+        Ok(len) if metadata.is_file() && len > 0 => len,
+        _ => return gxhash128_reader(file, seed),
+    };
+    // Knowing the length allows hashing in constant memory, as long as the file doesn't grow meanwhile
+    let hash = gxhash128_reader_with_len(&file, seed, len)?;
+    if (&file).read(&mut [0])? != 0 {
+        return Err(io::Error::other("File changed while being read"));
+    }
+    Ok(hash)
+}
+
+/// Formats a line of a sums file. Names with backslashes or line breaks are escaped, and their line starts with
+/// a backslash, as with coreutils.
+fn format_line(hash: u128, bits: u32, name: &str) -> String {
+    let digits = bits as usize / 4;
+    let hash = hash & (u128::MAX >> (128 - bits));
+    if name.contains(['\\', '\n', '\r']) {
+        let name = name.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+        format!("\\{hash:0digits$x}  {name}")
+    } else {
+        format!("{hash:0digits$x}  {name}")
+    }
+}
+
+/// Parses a line of a sums file into the checksum, its number of bits and the name of the file
+fn parse_line(line: &str) -> Option<(u128, u32, String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(line) => (true, line),
+        None => (false, line),
+    };
+    let (digits, name) = line.split_once(' ')?;
+    // Text and binary modes of coreutils, which are the same here
+    let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+
+    let bits = match digits.len() {
+        8 => 32,
+        16 => 64,
+        32 => 128,
+        _ => return None,
+    };
+    if name.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hash = u128::from_str_radix(digits, 16).ok()?;
+
+    let name = if escaped { unescape(name)? } else { name.to_string() };
+    Some((hash, bits, name))
+}
+
+fn unescape(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        unescaped.push(match c {
+            '\\' => match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                _ => return None,
+            },
+            c => c,
+        });
+    }
+    Some(unescaped)
+}
+
+/// Checks the checksums listed in a sums file
+fn check(sums: &str, options: &Options, stdin: &mut dyn Read, out: &mut dyn Write, err: &mut dyn Write) -> u8 {
+    let lines: Vec<String> = match read_lines(sums, stdin) {
+        Ok(lines) => lines,
+        Err(e) => {
+            let _ = writeln!(err, "gxhsum: {sums}: {e}");
+            return ERROR;
+        }
+    };
+
+    let (mut mismatches, mut unreadable, mut malformed) = (0, 0, 0);
+    for line in lines.iter().filter(|line| !line.is_empty()) {
+        let Some((expected, bits, name)) = parse_line(line) else {
+            malformed += 1;
+            continue;
+        };
+        // The standard input was already read for the sums, so it can't be hashed as well
+        let hashed = if sums == "-" && name == "-" {
+            Err(io::Error::other("Standard input already read for the checksums"))
+        } else {
+            hash(Path::new(&name), options.seed, stdin)
+        };
+        match hashed {
+            Ok(hash) if format_line(hash, bits, &name) == format_line(expected, bits, &name) => {
+                if !options.quiet {
+                    let _ = writeln!(out, "{name}: OK");
+                }
+            }
+            Ok(_) => {
+                mismatches += 1;
+                let _ = writeln!(out, "{name}: FAILED");
+            }
+            Err(e) => {
+                unreadable += 1;
+                let _ = writeln!(err, "gxhsum: {name}: {e}");
+                let _ = writeln!(out, "{name}: FAILED open or read");
+            }
+        }
+    }
+
+    let plural = |count: usize, singular: &'static str, plural: &'static str| if count == 1 { singular } else { plural };
+    if malformed > 0 {
+        let _ = writeln!(
+            err,
+            "gxhsum: WARNING: {malformed} {} improperly formatted",
+            plural(malformed, "line is", "lines are")
+        );
+    }
+    if unreadable > 0 {
+        let _ = writeln!(
+            err,
+            "gxhsum: WARNING: {unreadable} listed {} could not be read",
+            plural(unreadable, "file", "files")
+        );
+    }
+    if mismatches > 0 {
+        let _ = writeln!(
+            err,
+            "gxhsum: WARNING: {mismatches} computed {} did NOT match",
+            plural(mismatches, "checksum", "checksums")
+        );
+    }
+
+    if malformed == lines.iter().filter(|line| !line.is_empty()).count() {
+        let _ = writeln!(err, "gxhsum: {sums}: no properly formatted checksum lines found");
+        ERROR
+    } else if unreadable > 0 {
+        ERROR
+    } else if mismatches > 0 {
+        MISMATCH
+    } else {
+        SUCCESS
+    }
+}
+
+fn read_lines(path: &str, stdin: &mut dyn Read) -> io::Result<Vec<String>> {
+    let reader: Box<dyn BufRead + '_> = if path == "-" {
+        Box::new(BufReader::new(stdin))
+    } else {
+        Box::new(BufReader::new(File::open(path)?))
+    };
+    reader.lines().collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // A directory removed once the test is done
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("gxhsum-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn file(&self, name: &str, contents: &[u8]) -> String {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            path.to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn gxhsum(args: &[&str], stdin: &[u8]) -> (u8, String, String) {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = run(&args, &mut &stdin[..], &mut out, &mut err);
+        (code, String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+    }
+
+    #[test]
+    fn prints_coreutils_lines() {
+        let dir = TempDir::new("print");
+        let file = dir.file("hello.txt", b"hello world");
+
+        let (code, out, _) = gxhsum(&[&file], b"");
+        assert_eq!(SUCCESS, code);
+        assert_eq!(format!("{:032x}  {file}\n", gxhash::gxhash128(b"hello world", 0)), out);
+
+        let (_, out, _) = gxhsum(&["--bits", "64", "-s", "-42", &file], b"");
+        assert_eq!(format!("{:016x}  {file}\n", gxhash::gxhash64(b"hello world", -42)), out);
+
+        let (_, out, _) = gxhsum(&["--bits=32", "--seed=7", "-"], b"hello world");
+        assert_eq!(format!("{:08x}  -\n", gxhash::gxhash32(b"hello world", 7)), out);
+
+        let (_, out, _) = gxhsum(&[], b"hello world");
+        assert_eq!(format!("{:032x}  -\n", gxhash::gxhash128(b"hello world", 0)), out);
+    }
+
+    #[test]
+    fn checks_sums() {
+        let dir = TempDir::new("check");
+        let a = dir.file("a", b"a");
+        let b = dir.file("b", b"b");
+
+        let (_, sums, _) = gxhsum(&["-s", "3", &a, &b], b"");
+        let sums_file = dir.file("sums", sums.as_bytes());
+
+        let (code, out, _) = gxhsum(&["-c", "-s", "3", &sums_file], b"");
+        assert_eq!(SUCCESS, code);
+        assert_eq!(format!("{a}: OK\n{b}: OK\n"), out);
+
+        let (code, out, _) = gxhsum(&["--check", "--quiet", "--seed", "3"], sums.as_bytes());
+        assert_eq!((SUCCESS, String::new()), (code, out));
+
+        // Other seed
+        let (code, _, err) = gxhsum(&["-c", &sums_file], b"");
+        assert_eq!(MISMATCH, code);
+        assert!(err.contains("2 computed checksums did NOT match"));
+
+        // Modified file
+        dir.file("a", b"A");
+        let (code, out, _) = gxhsum(&["-c", "-s", "3", &sums_file], b"");
+        assert_eq!(MISMATCH, code);
+        assert_eq!(format!("{a}: FAILED\n{b}: OK\n"), out);
+
+        // Missing file, which is more severe than a mismatch
+        fs::remove_file(&b).unwrap();
+        let (code, out, err) = gxhsum(&["-c", "-s", "3", &sums_file], b"");
+        assert_eq!(ERROR, code);
+        assert_eq!(format!("{a}: FAILED\n{b}: FAILED open or read\n"), out);
+        assert!(err.contains("1 listed file could not be read"));
+
+        // Standard input listed in sums read from the standard input
+        let stdin_sums = format!("{:032x}  -\n", gxhash::gxhash128(b"", 3));
+        let (code, out, err) = gxhsum(&["-c", "-s", "3", "-"], stdin_sums.as_bytes());
+        assert_eq!(ERROR, code);
+        assert_eq!("-: FAILED open or read\n", out);
+        assert!(err.contains("gxhsum: -: Standard input already read for the checksums"));
+    }
+
+    #[test]
+    fn checks_sums_of_any_size() {
+        let dir = TempDir::new("sizes");
+        let file = dir.file("file", b"contents");
+        let sums: String = ["32", "64", "128"].iter().map(|bits| gxhsum(&["-b", bits, &file], b"").1).collect();
+
+        let (code, out, _) = gxhsum(&["-c"], sums.as_bytes());
+        assert_eq!(SUCCESS, code);
+        assert_eq!(3, out.matches(": OK").count());
+    }
+
+    #[test]
+    fn reports_read_errors() {
+        let dir = TempDir::new("errors");
+        let file = dir.file("file", b"contents");
+        let missing = dir.0.join("missing").to_string_lossy().into_owned();
+
+        let (code, out, err) = gxhsum(&[&missing, &file], b"");
+        assert_eq!(ERROR, code);
+        assert_eq!(1, out.lines().count());
+        assert!(err.contains(&missing));
+
+        // Directories are only hashed in recursive mode
+        let (code, _, _) = gxhsum(&[&dir.0.to_string_lossy()], b"");
+        assert_eq!(ERROR, code);
+
+        let (code, _, err) = gxhsum(&["-c"], b"not a checksum line\n");
+        assert_eq!(ERROR, code);
+        assert!(err.contains("no properly formatted checksum lines found"));
+
+        let (code, _, _) = gxhsum(&["--bits", "48"], b"");
+        assert_eq!(ERROR, code);
+        let (code, _, _) = gxhsum(&["--unknown"], b"");
+        assert_eq!(ERROR, code);
+    }
+
+    #[test]
+    fn recursive_order_is_deterministic() {
+        let dir = TempDir::new("recursive");
+        for name in ["b", "a/z", "a/y", "c/d/e", "B"] {
+            dir.file(name, name.as_bytes());
+        }
+
+        let (code, out, _) = gxhsum(&["-r", &dir.0.to_string_lossy()], b"");
+        assert_eq!(SUCCESS, code);
+        let names: Vec<&str> = out.lines().map(|line| line.rsplit('/').next().unwrap()).collect();
+        assert_eq!(vec!["B", "y", "z", "b", "e"], names);
+
+        // Out of the hashed directory, so that it isn't listed in its own sums
+        let sums_dir = TempDir::new("recursive-sums");
+        let sums_file = sums_dir.file("sums", out.as_bytes());
+        let (code, _, _) = gxhsum(&["-c", &sums_file], b"");
+        assert_eq!(SUCCESS, code);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hashes_files_without_length() {
+        let dir = TempDir::new("fifo");
+        let fifo = dir.0.join("fifo");
+        let path = std::ffi::CString::new(fifo.to_string_lossy().as_bytes()).unwrap();
+        assert_eq!(0, unsafe { libc::mkfifo(path.as_ptr(), 0o600) });
+        let writer = std::thread::spawn({
+            let fifo = fifo.clone();
+            move || fs::write(fifo, b"hello world").unwrap()
+        });
+
+        let (code, out, _) = gxhsum(&[&fifo.to_string_lossy()], b"");
+        writer.join().unwrap();
+        assert_eq!(SUCCESS, code);
+        assert_eq!(format!("{:032x}  {}\n", gxhash::gxhash128(b"hello world", 0), fifo.display()), out);
+
+        // Files of procfs are regular files with a length of 0
+        if cfg!(target_os = "linux") {
+            let contents = fs::read("/proc/version").unwrap();
+            assert_eq!(0, fs::metadata("/proc/version").unwrap().len());
+            let (code, out, _) = gxhsum(&["/proc/version"], b"");
+            assert_eq!(SUCCESS, code);
+            assert_eq!(format!("{:032x}  /proc/version\n", gxhash::gxhash128(&contents, 0)), out);
+        }
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let line = format_line(42, 32, "new\nline\\");
+        assert_eq!("\\0000002a  new\\nline\\\\", line);
+        assert_eq!(Some((42, 32, "new\nline\\".to_string())), parse_line(&line));
+        assert_eq!(Some((42, 64, "binary".to_string())), parse_line("000000000000002a *binary"));
+        assert_eq!(None, parse_line("00000002a  odd number of digits"));
+        assert_eq!(None, parse_line("0000002a no double space"));
+    }
+}
@@ -0,0 +1,200 @@
+use core::hash::{Hash, Hasher};
+
+use crate::{gxhash64, GxHasher};
+
+/// Hashes the input with [`gxhash64`] using the provided seed, and maps the hash to a bucket in `0..n`.
+///
+/// Unlike `gxhash64(input, seed) % n`, which favors the lowest buckets when `n` isn't a power of two and costs a
+/// division, the hash is mapped with a multiplication and a shift (as in Lemire's "fastrange"), which is as uniform
+/// as the hash itself.
+///
+/// Changing `n` moves most inputs to other buckets: [`jump_consistent_hash`] or [`rendezvous`] keep most of them in
+/// place when the buckets are shards that come and go.
+///
+/// # Panics
+/// Panics if `n` is 0.
+///
+/// # Example
+///
+/// ```
+/// let shard = gxhash::gxhash_bucket(b"user:1234", 42, 12);
+/// assert!(shard < 12);
+/// ```
+#[inline]
+pub fn gxhash_bucket(input: &[u8], seed: i64, n: u64) -> u64 {
+    assert!(n > 0, "there must be at least one bucket");
+    ((gxhash64(input, seed) as u128 * n as u128) >> 64) as u64
+}
+
+/// Maps the hash of a key to a bucket in `0..buckets`, with the jump consistent hash of Lamping and Veach: when
+/// the number of buckets grows from `n` to `n + 1`, only `1 / (n + 1)` of the keys move, all to the new bucket.
+///
+/// Buckets are numbered, so they can only be added or removed at the end of the range. Shards that can leave in
+/// any order are selected with [`rendezvous`] instead. Buckets are the same as with other implementations of the
+/// algorithm given the same hashes.
+///
+/// # Panics
+/// Panics if `buckets` is 0.
+///
+/// # Example
+///
+/// ```
+/// let hash = gxhash::gxhash64(b"user:1234", 42);
+/// let shard = gxhash::jump_consistent_hash(hash, 12);
+/// assert!(shard < 12);
+/// ```
+#[inline]
+pub fn jump_consistent_hash(key_hash: u64, buckets: u32) -> u32 {
+    assert!(buckets > 0, "there must be at least one bucket");
+    let (mut key, mut b, mut j) = (key_hash, 0i64, 0i64);
+    while j < buckets as i64 {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1i64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    b as u32
+}
+
+/// Selects the node of a key among the given nodes, with rendezvous (highest random weight) hashing: each node is
+/// scored by hashing it with a [`GxHasher`] seeded with the hash of the key, and the node with the highest score
+/// is selected. Returns `None` if there are no nodes.
+///
+/// Removing a node only moves the keys it was selected for, and adding a node only moves keys to it, whatever
+/// the order of the nodes. Selecting a node takes a hash per node, so it suits short lists of nodes.
+///
+/// # Example
+///
+/// ```
+/// let nodes = ["cache-a", "cache-b", "cache-c"];
+/// let hash = gxhash::gxhash64(b"user:1234", 42);
+/// let node = gxhash::rendezvous(hash, &nodes).unwrap();
+///
+/// // Removing another node doesn't move the key
+/// let other = nodes.iter().find(|&n| n != node).unwrap();
+/// assert_eq!(node, gxhash::rendezvous(hash, &[*other, *node]).unwrap());
+/// ```
+#[inline]
+pub fn rendezvous<N: Hash>(key_hash: u64, nodes: &[N]) -> Option<&N> {
+    nodes.iter().max_by_key(|node| {
+        let mut hasher = GxHasher::with_seed(key_hash as i64);
+        node.hash(&mut hasher);
+        hasher.finish()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // Whether the counts of keys in each bucket are consistent with a uniform distribution: their chi-squared
+    // statistic is within 5 standard deviations of its expected value
+    fn is_even(counts: &[usize]) -> bool {
+        let mean = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+        let chi_squared: f64 = counts.iter().map(|&count| (count as f64 - mean).powi(2) / mean).sum();
+        let degrees = (counts.len() - 1) as f64;
+        chi_squared < degrees + 5.0 * (2.0 * degrees).sqrt()
+    }
+
+    #[test]
+    fn bucket_is_multiply_shift() {
+        for i in 0u64..1000 {
+            let n = i * 7919 + 1;
+            let bucket = gxhash_bucket(&i.to_le_bytes(), 42, n);
+            assert!(bucket < n);
+            assert_eq!(((gxhash64(&i.to_le_bytes(), 42) as u128 * n as u128) >> 64) as u64, bucket);
+        }
+        assert_eq!(0, gxhash_bucket(b"hello world", 42, 1));
+        assert!(gxhash_bucket(b"hello world", 42, u64::MAX) < u64::MAX);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn buckets_are_even() {
+        for n in [2, 7, 10, 100] {
+            let mut counts = vec![0; n];
+            for i in 0..100_000u32 {
+                counts[gxhash_bucket(&i.to_le_bytes(), 0, n as u64) as usize] += 1;
+            }
+            assert!(is_even(&counts), "uneven buckets for n = {n}: {counts:?}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn bucket_rejects_no_buckets() {
+        gxhash_bucket(b"hello world", 42, 0);
+    }
+
+    #[test]
+    fn jump_matches_published_implementation() {
+        // Same as the test cases of the Go implementation (github.com/dgryski/go-jump)
+        for (key, buckets, expected) in [(1, 1, 0), (42, 57, 43), (0xDEAD10CC, 1, 0), (0xDEAD10CC, 666, 361), (256, 1024, 520)] {
+            assert_eq!(expected, jump_consistent_hash(key, buckets), "key {key}, {buckets} buckets");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn jump_moves_keys_to_new_bucket_only() {
+        let keys: Vec<u64> = (0..100_000u32).map(|i| gxhash64(&i.to_le_bytes(), 0)).collect();
+        for n in [1, 2, 9, 10, 99] {
+            let mut moved = 0;
+            let mut counts = vec![0; n as usize + 1];
+            for &key in &keys {
+                let (before, after) = (jump_consistent_hash(key, n), jump_consistent_hash(key, n + 1));
+                assert!(before < n && after <= n);
+                if before != after {
+                    assert_eq!(n, after, "key moved to an existing bucket");
+                    moved += 1;
+                }
+                counts[after as usize] += 1;
+            }
+            let expected = keys.len() as f64 / (n + 1) as f64;
+            assert!((moved as f64 - expected).abs() < 5.0 * expected.sqrt(), "{moved} keys moved for {n} buckets");
+            assert!(is_even(&counts), "uneven buckets for n = {}: {counts:?}", n + 1);
+        }
+    }
+
+    #[test]
+    fn rendezvous_selects_nothing_without_nodes() {
+        assert_eq!(None, rendezvous::<u32>(42, &[]));
+        assert_eq!(Some(&"a"), rendezvous(42, &["a"]));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rendezvous_moves_keys_of_changed_nodes_only() {
+        let nodes: Vec<String> = (0..10).map(|i| format!("node-{i}")).collect();
+        // Fixed keys, so that the statistical checks below can't fail by chance on some runs
+        let keys: Vec<u64> = (0..50_000u32).map(|i| gxhash64(&i.to_le_bytes(), 0)).collect();
+
+        let mut counts = vec![0; nodes.len()];
+        let selected: Vec<&String> = keys.iter().map(|&key| rendezvous(key, &nodes).unwrap()).collect();
+        for node in &selected {
+            counts[nodes.iter().position(|n| &n == node).unwrap()] += 1;
+        }
+        assert!(is_even(&counts), "uneven nodes: {counts:?}");
+
+        // Removing a node, wherever it is in the list, only moves its keys
+        let removed = &nodes[3];
+        let mut remaining: Vec<&String> = nodes.iter().filter(|&n| n != removed).collect();
+        remaining.reverse();
+        for (&key, &node) in keys.iter().zip(&selected) {
+            let new_node = *rendezvous(key, &remaining).unwrap();
+            assert!(new_node == node || node == removed);
+        }
+
+        // Adding a node only moves keys to it, about as many as it gets its share
+        let mut added: Vec<&String> = nodes.iter().collect();
+        let new = "node-10".to_string();
+        added.insert(5, &new);
+        let moved = keys.iter().zip(&selected).filter(|&(&key, &node)| {
+            let new_node = *rendezvous(key, &added).unwrap();
+            assert!(new_node == node || new_node == &new);
+            new_node != node
+        });
+        let expected = keys.len() as f64 / added.len() as f64;
+        assert!((moved.count() as f64 - expected).abs() < 5.0 * expected.sqrt());
+    }
+}
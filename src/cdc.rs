@@ -0,0 +1,398 @@
+//! Content-defined chunking, splitting inputs into chunks hashed with [`gxhash128`].
+//!
+//! Chunk boundaries are found with a FastCDC-style gear rolling hash: they depend on the content around them
+//! rather than on their offset, so that inserting or removing bytes in an input only changes the chunks around
+//! the edit. This makes the chunks of similar inputs, such as successive versions of a file, mostly the same,
+//! and suited for deduplication. Each chunk is hashed right after its boundary is found, while it's still in
+//! the cache, in a single pass over the input.
+//!
+//! # Example
+//!
+//! ```
+//! use gxhash::cdc::Chunker;
+//!
+//! let data = vec![42u8; 100_000];
+//! for chunk in Chunker::default().chunks(&data, 1234) {
+//!     println!("{} bytes at {}: {:x}", chunk.len, chunk.offset, chunk.hash);
+//! }
+//! ```
+
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+use crate::gxhash128;
+
+/// A chunk of an input, as found by a [`Chunker`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chunk {
+    /// Position of the first byte of the chunk in the input
+    pub offset: u64,
+    /// Number of bytes in the chunk
+    pub len: usize,
+    /// [`gxhash128`] of the bytes of the chunk
+    pub hash: u128,
+}
+
+/// Finds content-defined chunk boundaries, for chunks of sizes between a minimum and a maximum and averaging
+/// around a given size.
+///
+/// Boundaries only depend on the input and on the sizes, never on the seed the chunks are hashed with, and are
+/// stable across versions of the crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Chunker {
+    min_size: usize,
+    avg_size: usize,
+    max_size: usize,
+    // Masks of the rolling hash for chunks smaller and larger than the average size.
+    // Cut points are harder to find before the average size and easier after it (normalized chunking), which
+    // narrows the distribution of chunk sizes around the average.
+    mask_small: u64,
+    mask_large: u64,
+}
+
+// Number of bits the masks differ by from the average, known as level 2 normalization in FastCDC
+const NORMALIZATION: u32 = 2;
+
+impl Chunker {
+    /// Creates a chunker for chunks of `min_size` to `max_size` bytes, averaging around `avg_size` bytes.
+    /// Only the last chunk of an input may be smaller than `min_size`.
+    ///
+    /// # Panics
+    /// Panics unless `0 < min_size <= avg_size <= max_size`, and `avg_size` is a power of two of at least 64.
+    pub fn new(min_size: usize, avg_size: usize, max_size: usize) -> Chunker {
+        assert!(
+            0 < min_size && min_size <= avg_size && avg_size <= max_size,
+            "chunk sizes must be ordered and non-zero"
+        );
+        assert!(
+            avg_size.is_power_of_two() && avg_size >= 64,
+            "average chunk size must be a power of two of at least 64 bytes"
+        );
+
+        // The highest bits of the rolling hash depend on the most bytes, they are the ones cut points look at
+        let bits = avg_size.ilog2();
+        Chunker {
+            min_size,
+            avg_size,
+            max_size,
+            mask_small: !0 << (64 - bits - NORMALIZATION),
+            mask_large: !0 << (64 - bits + NORMALIZATION),
+        }
+    }
+
+    /// Minimum size of chunks, except for the last one
+    #[inline]
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Size chunks average around
+    #[inline]
+    pub fn avg_size(&self) -> usize {
+        self.avg_size
+    }
+
+    /// Maximum size of chunks
+    #[inline]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Splits the input into chunks, hashed using the provided seed.
+    #[inline]
+    pub fn chunks<'a>(&self, input: &'a [u8], seed: i64) -> Chunks<'a> {
+        Chunks {
+            chunker: *self,
+            input,
+            offset: 0,
+            seed,
+        }
+    }
+
+    /// Splits the bytes read from `reader` into chunks, hashed using the provided seed.
+    /// Bytes are read into a buffer of a few times the maximum chunk size, which is the only memory used.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn read_chunks<R: Read>(&self, reader: R, seed: i64) -> ReadChunks<R> {
+        ReadChunks {
+            chunker: *self,
+            reader,
+            seed,
+            buffer: vec![0; 4 * self.max_size].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Size of the next chunk of the input, which is the whole input when it is smaller than the maximum size
+    /// and has no cut point.
+    fn cut(&self, input: &[u8]) -> usize {
+        if input.len() <= self.min_size {
+            return input.len();
+        }
+        let end = input.len().min(self.max_size);
+        let normal = end.min(self.avg_size);
+
+        // The rolling hash only starts after the minimum size, as there can't be a cut point before
+        let mut hash = 0u64;
+        for (i, &byte) in input.iter().enumerate().take(normal).skip(self.min_size) {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            if hash & self.mask_small == 0 {
+                return i + 1;
+            }
+        }
+        for (i, &byte) in input.iter().enumerate().take(end).skip(normal) {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            if hash & self.mask_large == 0 {
+                return i + 1;
+            }
+        }
+        end
+    }
+}
+
+impl Default for Chunker {
+    /// Creates a chunker for chunks of 2KiB to 64KiB, averaging around 8KiB, as in the FastCDC paper.
+    #[inline]
+    fn default() -> Chunker {
+        Chunker::new(2 * 1024, 8 * 1024, 64 * 1024)
+    }
+}
+
+/// Iterator over the chunks of an input, created by [`Chunker::chunks`]
+#[derive(Clone, Debug)]
+pub struct Chunks<'a> {
+    chunker: Chunker,
+    input: &'a [u8],
+    offset: u64,
+    seed: i64,
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        if self.input.is_empty() {
+            return None;
+        }
+        let (bytes, rest) = self.input.split_at(self.chunker.cut(self.input));
+        let chunk = Chunk {
+            offset: self.offset,
+            len: bytes.len(),
+            hash: gxhash128(bytes, self.seed),
+        };
+        self.input = rest;
+        self.offset += bytes.len() as u64;
+        Some(chunk)
+    }
+}
+
+/// Iterator over the chunks of the bytes of a reader, created by [`Chunker::read_chunks`].
+/// Errors of the reader are returned as they happen, after which iterating again resumes reading.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ReadChunks<R> {
+    chunker: Chunker,
+    reader: R,
+    seed: i64,
+    buffer: Box<[u8]>,
+    // Bytes read but not chunked yet
+    start: usize,
+    end: usize,
+    offset: u64,
+    eof: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ReadChunks<R> {
+    // Reads until there are enough bytes for the largest chunk, or the end of the reader is reached
+    fn fill(&mut self) -> io::Result<()> {
+        // Bytes of an incomplete chunk are only moved back to the start once there isn't enough room left for the
+        // largest chunk, that is once in a few maximum chunk sizes
+        if self.buffer.len() - self.start < self.chunker.max_size {
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+
+        while self.end - self.start < self.chunker.max_size && !self.eof {
+            match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(0) => self.eof = true,
+                Ok(len) => self.end += len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Iterator for ReadChunks<R> {
+    type Item = io::Result<Chunk>;
+
+    fn next(&mut self) -> Option<io::Result<Chunk>> {
+        if let Err(e) = self.fill() {
+            return Some(Err(e));
+        }
+        if self.start == self.end {
+            return None;
+        }
+        let bytes = &self.buffer[self.start..self.end];
+        let len = self.chunker.cut(bytes);
+        let chunk = Chunk {
+            offset: self.offset,
+            len,
+            hash: gxhash128(&bytes[..len], self.seed),
+        };
+        self.start += len;
+        self.offset += len as u64;
+        Some(Ok(chunk))
+    }
+}
+
+/// Random values the rolling hash adds for each byte, from a SplitMix64 generator with a fixed seed.
+/// They are part of the definition of chunk boundaries, and must never change.
+const GEAR: [u64; 256] = {
+    let mut gear = [0u64; 256];
+    let mut state = 0x6778_6861_7368_6364u64;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        gear[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    gear
+};
+
+#[cfg(test)]
+mod tests {
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    // Seeded, so that the statistical checks on chunk boundaries can't fail by chance on some runs
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        StdRng::seed_from_u64(len as u64).fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    fn assert_chunks_cover(chunker: &Chunker, input: &[u8], chunks: &[Chunk]) {
+        let mut offset = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(offset, chunk.offset as usize);
+            assert!(chunk.len <= chunker.max_size(), "chunk of {} bytes", chunk.len);
+            assert!(chunk.len >= chunker.min_size() || i == chunks.len() - 1, "chunk of {} bytes", chunk.len);
+            assert_eq!(gxhash128(&input[offset..offset + chunk.len], 42), chunk.hash);
+            offset += chunk.len;
+        }
+        assert_eq!(input.len(), offset);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn chunks_cover_input() {
+        let chunker = Chunker::new(256, 1024, 4096);
+        for len in [0, 1, 255, 256, 257, 4096, 4097, 100_000] {
+            let input = random_bytes(len);
+            let chunks: Vec<Chunk> = chunker.chunks(&input, 42).collect();
+            assert_chunks_cover(&chunker, &input, &chunks);
+        }
+
+        // Inputs without cut points are split at the maximum size
+        let input = vec![0u8; 10_000];
+        let lens: Vec<usize> = chunker.chunks(&input, 42).map(|chunk| chunk.len).collect();
+        assert_eq!(vec![4096, 4096, 1808], lens);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn chunks_average_around_avg_size() {
+        let chunker = Chunker::default();
+        let input = random_bytes(8 << 20);
+        let count = chunker.chunks(&input, 0).count();
+        let average = input.len() / count;
+        assert!(
+            average > chunker.avg_size() / 2 && average < chunker.avg_size() * 2,
+            "average chunk size of {average}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn chunks_are_content_defined() {
+        let chunker = Chunker::new(256, 1024, 4096);
+        let input = random_bytes(200_000);
+        let mut edited = input.clone();
+        edited.splice(100_000..100_000, [1, 2, 3]);
+        edited.drain(10..20);
+
+        // Only the chunks around the edits change, whatever their offset
+        let hashes: std::collections::HashSet<u128> = chunker.chunks(&input, 0).map(|chunk| chunk.hash).collect();
+        let edited_chunks: Vec<Chunk> = chunker.chunks(&edited, 0).collect();
+        let changed = edited_chunks.iter().filter(|chunk| !hashes.contains(&chunk.hash)).count();
+        assert!(changed <= 4, "{changed} chunks out of {} changed", edited_chunks.len());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore)]
+    fn read_chunks_match_chunks() {
+        use std::io::Cursor;
+
+        struct Trickle<'a>(&'a [u8]);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let len = rand::thread_rng().gen_range(0..=700).min(buf.len()).min(self.0.len());
+                if len == 0 && !self.0.is_empty() && !buf.is_empty() {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+                buf[..len].copy_from_slice(&self.0[..len]);
+                self.0 = &self.0[len..];
+                Ok(len)
+            }
+        }
+
+        let chunker = Chunker::new(256, 1024, 4096);
+        for len in [0, 1, 4096, 4097, 100_000] {
+            let input = random_bytes(len);
+            let expected: Vec<Chunk> = chunker.chunks(&input, 42).collect();
+            let chunks: Vec<Chunk> = chunker.read_chunks(Cursor::new(&input), 42).collect::<io::Result<_>>().unwrap();
+            assert_eq!(expected, chunks);
+            let chunks: Vec<Chunk> = chunker.read_chunks(Trickle(&input), 42).collect::<io::Result<_>>().unwrap();
+            assert_eq!(expected, chunks);
+        }
+    }
+
+    #[test]
+    fn boundaries_are_stable() {
+        // Pseudo-random bytes from a linear congruential generator
+        let mut state = 0u64;
+        let input: Vec<u8> = (0..5000)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect();
+        let lens: Vec<usize> = Chunker::new(64, 256, 1024).chunks(&input, 0).map(|chunk| chunk.len).collect();
+        assert_eq!(
+            vec![257, 291, 246, 260, 295, 296, 308, 435, 279, 355, 166, 274, 280, 344, 311, 324, 267, 12],
+            lens
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn average_size_must_be_a_power_of_two() {
+        Chunker::new(1000, 3000, 10_000);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::gxhash::platform::*;
+use crate::gxhash::*;
+
+// Domains of the combinations, so that the same hashes combined for different purposes give different hashes
+const ORDERED: u8 = 1;
+const UNORDERED: u8 = 2;
+const MULTISET_ELEMENT: u8 = 3;
+const MULTISET: u8 = 4;
+
+#[inline(always)]
+fn combine_in(a: u128, b: u128, domain: u8) -> u128 {
+    unsafe {
+        let hash = combine(create_key(&a.to_le_bytes()), create_key(&b.to_le_bytes()), load_u8(domain));
+        let p = &hash as *const State as *const u128;
+        u128::from_le(*p)
+    }
+}
+
+/// Combines two hashes into one, in a way that depends on their order, such as for the fields of a tuple or
+/// the children of a node in a Merkle tree. Unlike a xor, combining equal hashes or swapping them doesn't give
+/// predictable results.
+///
+/// # Example
+///
+/// ```
+/// use gxhash::{combine_ordered, gxhash128};
+///
+/// let (left, right) = (gxhash128(b"left", 1234), gxhash128(b"right", 1234));
+/// let parent = combine_ordered(left, right);
+/// assert_ne!(parent, combine_ordered(right, left));
+/// ```
+#[inline(always)]
+pub fn combine_ordered(a: u128, b: u128) -> u128 {
+    combine_in(a, b, ORDERED)
+}
+
+/// Combines two hashes into one, regardless of their order, such as for a pair of interchangeable values.
+/// Combinations of pairs of hashes are never the same as the [`combine_ordered`] of the same pairs.
+///
+/// Combinations are commutative but not associative, so folding more than two hashes still depends on their
+/// order. Hashes of sets or multisets are computed with a [`GxMultisetHash`] instead.
+///
+/// # Example
+///
+/// ```
+/// use gxhash::{combine_unordered, gxhash128};
+///
+/// let (a, b) = (gxhash128(b"a", 1234), gxhash128(b"b", 1234));
+/// assert_eq!(combine_unordered(a, b), combine_unordered(b, a));
+/// ```
+#[inline(always)]
+pub fn combine_unordered(a: u128, b: u128) -> u128 {
+    combine_in(a.min(b), a.max(b), UNORDERED)
+}
+
+/// An incremental hash of a multiset of hashes, such as the hashes of the elements of a set, which doesn't depend
+/// on the order the hashes are inserted in. Hashes can also be removed, so that the hash of a collection is kept
+/// up to date as it changes, without hashing all of its elements again.
+///
+/// Each hash is mixed and added to a sum, along with the number of hashes, which are then combined by
+/// [`GxMultisetHash::finish`]. Inserting a hash multiple times changes the multiset, and removing a hash that
+/// isn't part of the multiset isn't detected: the multiset can then only be told apart from others once the
+/// hash is inserted back.
+///
+/// # Example
+///
+/// ```
+/// use gxhash::{gxhash128, GxMultisetHash};
+///
+/// let (a, b, c) = (gxhash128(b"a", 1234), gxhash128(b"b", 1234), gxhash128(b"c", 1234));
+///
+/// let mut multiset = GxMultisetHash::from_iter([a, b, c]);
+/// multiset.remove(b);
+///
+/// assert_eq!(multiset.finish(), GxMultisetHash::from_iter([c, a]).finish());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GxMultisetHash {
+    sum: u128,
+    len: u64,
+}
+
+impl GxMultisetHash {
+    /// Creates the hash of an empty multiset
+    #[inline]
+    pub fn new() -> GxMultisetHash {
+        GxMultisetHash::default()
+    }
+
+    /// Inserts a hash into the multiset
+    #[inline]
+    pub fn insert(&mut self, hash: u128) {
+        self.sum = self.sum.wrapping_add(element(hash));
+        self.len = self.len.wrapping_add(1);
+    }
+
+    /// Removes a hash from the multiset, which is the same as never having inserted it
+    #[inline]
+    pub fn remove(&mut self, hash: u128) {
+        self.sum = self.sum.wrapping_sub(element(hash));
+        self.len = self.len.wrapping_sub(1);
+    }
+
+    /// Inserts all the hashes of another multiset into this one
+    #[inline]
+    pub fn merge(&mut self, other: &GxMultisetHash) {
+        self.sum = self.sum.wrapping_add(other.sum);
+        self.len = self.len.wrapping_add(other.len);
+    }
+
+    /// Number of hashes in the multiset, counting duplicates
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the multiset is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Hash of the multiset
+    #[inline]
+    pub fn finish(&self) -> u128 {
+        combine_in(self.sum, self.len as u128, MULTISET)
+    }
+}
+
+// Hashes are mixed before being added, so that related hashes don't cancel out in the sum
+#[inline(always)]
+fn element(hash: u128) -> u128 {
+    combine_in(hash, 0, MULTISET_ELEMENT)
+}
+
+impl Extend<u128> for GxMultisetHash {
+    fn extend<T: IntoIterator<Item = u128>>(&mut self, hashes: T) {
+        for hash in hashes {
+            self.insert(hash);
+        }
+    }
+}
+
+impl FromIterator<u128> for GxMultisetHash {
+    fn from_iter<T: IntoIterator<Item = u128>>(hashes: T) -> GxMultisetHash {
+        let mut multiset = GxMultisetHash::new();
+        multiset.extend(hashes);
+        multiset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn ordered_depends_on_order() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let (a, b) = (rng.gen(), rng.gen());
+            assert_ne!(combine_ordered(a, b), combine_ordered(b, a));
+            assert_ne!(combine_ordered(a, b), combine_unordered(a, b));
+        }
+        assert_ne!(combine_ordered(0, 0), 0);
+        assert_ne!(combine_ordered(42, 42), combine_ordered(7, 7));
+    }
+
+    #[test]
+    fn unordered_is_commutative() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let (a, b) = (rng.gen(), rng.gen());
+            assert_eq!(combine_unordered(a, b), combine_unordered(b, a));
+        }
+    }
+
+    // Same score as the avalanche of benches/quality: 0 when flipping any bit of either hash flips half of the
+    // bits of the combination on average, 1 when it flips none or all of them
+    fn avalanche(combine: fn(u128, u128) -> u128) -> f64 {
+        const ITERATIONS: usize = 1000;
+
+        let mut rng = rand::thread_rng();
+        let mut scores_sum = 0f64;
+
+        for _ in 0..ITERATIONS {
+            let (a, b): (u128, u128) = (rng.gen(), rng.gen());
+            let hash = combine(a, b);
+            for i in 0..128 {
+                scores_sum += (hash ^ combine(a ^ (1 << i), b)).count_ones() as f64 / 128.0;
+                scores_sum += (hash ^ combine(a, b ^ (1 << i))).count_ones() as f64 / 128.0;
+            }
+        }
+
+        (1.0 - 2.0 * (scores_sum / (ITERATIONS * 2 * 128) as f64)).abs()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn combinations_avalanche() {
+        assert!(avalanche(combine_ordered) < 0.001);
+        assert!(avalanche(combine_unordered) < 0.001);
+        assert!(avalanche(|a, b| GxMultisetHash::from_iter([a, b]).finish()) < 0.001);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn combinations_of_small_hashes_dont_collide() {
+        // Low-entropy hashes, such as identifiers, are the ones a xor combines the worst
+        let mut ordered = HashSet::new();
+        let mut unordered = HashSet::new();
+        for a in 0..256 {
+            for b in 0..256 {
+                ordered.insert(combine_ordered(a, b));
+                unordered.insert(combine_unordered(a, b));
+            }
+        }
+        assert_eq!(256 * 256, ordered.len());
+        assert_eq!(256 * 257 / 2, unordered.len());
+    }
+
+    #[test]
+    fn multiset_is_independent_of_order() {
+        let mut rng = rand::thread_rng();
+        let mut hashes: Vec<u128> = (0..100).map(|_| rng.gen()).collect();
+        let expected = GxMultisetHash::from_iter(hashes.iter().copied());
+
+        for _ in 0..10 {
+            hashes.shuffle(&mut rng);
+            assert_eq!(expected.finish(), GxMultisetHash::from_iter(hashes.iter().copied()).finish());
+        }
+
+        let (left, right) = hashes.split_at(42);
+        let mut merged = GxMultisetHash::from_iter(left.iter().copied());
+        merged.merge(&GxMultisetHash::from_iter(right.iter().copied()));
+        assert_eq!(expected.finish(), merged.finish());
+        assert_eq!(100, merged.len());
+    }
+
+    #[test]
+    fn multiset_removal_undoes_insertion() {
+        let mut rng = rand::thread_rng();
+        let hashes: Vec<u128> = (0..100).map(|_| rng.gen()).collect();
+
+        let mut multiset = GxMultisetHash::from_iter(hashes.iter().copied());
+        for hash in &hashes[50..] {
+            multiset.remove(*hash);
+        }
+        assert_eq!(GxMultisetHash::from_iter(hashes[..50].iter().copied()).finish(), multiset.finish());
+
+        for hash in &hashes[..50] {
+            multiset.remove(*hash);
+        }
+        assert!(multiset.is_empty());
+        assert_eq!(GxMultisetHash::new().finish(), multiset.finish());
+    }
+
+    #[test]
+    fn multiset_counts_duplicates() {
+        let (a, b) = (gxhash128(b"a", 0), gxhash128(b"b", 0));
+        let once = GxMultisetHash::from_iter([a, b]);
+        let twice = GxMultisetHash::from_iter([a, a, b]);
+        assert_ne!(once.finish(), twice.finish());
+        assert_ne!(GxMultisetHash::from_iter([a, a]).finish(), GxMultisetHash::from_iter([b, b]).finish());
+        assert_ne!(GxMultisetHash::new().finish(), GxMultisetHash::from_iter([0]).finish());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn multisets_of_powerset_dont_collide() {
+        // Every subset of a few small hashes, as in the powerset collisions of benches/quality
+        let hashes: Vec<u128> = (0..16).collect();
+        let mut set = HashSet::new();
+        for subset in 0..1u32 << hashes.len() {
+            let multiset = GxMultisetHash::from_iter(hashes.iter().copied().filter(|hash| subset & (1 << hash) != 0));
+            set.insert(multiset.finish());
+        }
+        assert_eq!(1 << hashes.len(), set.len());
+    }
+
+    #[test]
+    fn combinations_are_stable() {
+        assert_eq!(8119127538655552618484629698559321443, combine_ordered(1, 2));
+        assert_eq!(75288027228542771167512894700925348968, combine_unordered(1, 2));
+        assert_eq!(71026806876775375257203598248558294458, GxMultisetHash::from_iter([1, 2, 3]).finish());
+    }
+}
@@ -0,0 +1,303 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{gxhash128, gxhash64};
+
+/// Error returned when a digest can't be parsed from a string or a slice of bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseDigestError(());
+
+impl fmt::Display for ParseDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid digest, expected hexadecimal digits or bytes of the width of the digest")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseDigestError {}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+// The digest types only differ by the width of their integer
+macro_rules! digest {
+    ($(#[$attr:meta])* $name:ident, $type:ty, $hash:ident) => {
+        $(#[$attr])*
+        ///
+        /// Digests are compared as integers: equality is not constant-time, and must not be relied upon to compare
+        /// secrets.
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($type);
+
+        impl $name {
+            /// Number of bytes of the digest
+            pub const BYTES: usize = core::mem::size_of::<$type>();
+
+            #[doc = concat!("Hashes the input with [`", stringify!($hash), "`] using the provided seed.")]
+            #[inline]
+            pub fn of(input: &[u8], seed: i64) -> $name {
+                $name($hash(input, seed))
+            }
+
+            #[doc = concat!("Creates a digest from the value returned by [`", stringify!($hash), "`].")]
+            #[inline]
+            pub const fn from_value(value: $type) -> $name {
+                $name(value)
+            }
+
+            #[doc = concat!("Value of the digest, as returned by [`", stringify!($hash), "`].")]
+            #[inline]
+            pub const fn value(self) -> $type {
+                self.0
+            }
+
+            /// Bytes of the digest, in little endian order. This is the canonical binary representation of
+            /// digests, the same on every platform.
+            #[inline]
+            pub const fn to_le_bytes(self) -> [u8; Self::BYTES] {
+                self.0.to_le_bytes()
+            }
+
+            /// Creates a digest from its bytes in little endian order, as returned by `to_le_bytes`.
+            #[inline]
+            pub const fn from_le_bytes(bytes: [u8; Self::BYTES]) -> $name {
+                $name(<$type>::from_le_bytes(bytes))
+            }
+
+            /// Creates a digest from a slice of its bytes in little endian order, such as read from a database.
+            /// Fails if the slice is not exactly the size of the digest.
+            #[inline]
+            pub fn from_bytes(bytes: &[u8]) -> Result<$name, ParseDigestError> {
+                bytes.try_into().map($name::from_le_bytes).map_err(|_| ParseDigestError(()))
+            }
+
+            // Digits of the value, most significant first
+            fn hex(self) -> [u8; 2 * Self::BYTES] {
+                core::array::from_fn(|i| HEX_DIGITS[(self.0 >> (4 * (2 * Self::BYTES - 1 - i))) as usize & 0xf])
+            }
+        }
+
+        impl From<$type> for $name {
+            #[inline]
+            fn from(value: $type) -> $name {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for $type {
+            #[inline]
+            fn from(digest: $name) -> $type {
+                digest.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            /// Formats the value of the digest as lowercase hexadecimal digits, zero-padded to the width of the
+            /// digest, as with `{:032x}` for 128-bit digests. This is the canonical textual representation of
+            /// digests, suited for filenames.
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                // Digits are always ASCII. Padding honors the width, fill and alignment flags, as for strings.
+                f.pad(core::str::from_utf8(&self.hex()).unwrap())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), self)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseDigestError;
+
+            /// Parses the canonical representation of a digest, as formatted by `Display`. Uppercase digits are
+            /// accepted, but signs, prefixes and digits missing for the full width are not.
+            fn from_str(s: &str) -> Result<$name, ParseDigestError> {
+                if s.len() != 2 * Self::BYTES || !s.bytes().all(|digit| digit.is_ascii_hexdigit()) {
+                    return Err(ParseDigestError(()));
+                }
+                <$type>::from_str_radix(s, 16).map($name).map_err(|_| ParseDigestError(()))
+            }
+        }
+
+        // Digests are serialized as their canonical textual representation in human-readable formats, such as
+        // JSON, and as their canonical binary representation otherwise
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(core::str::from_utf8(&self.hex()).unwrap())
+                } else {
+                    serializer.serialize_bytes(&self.to_le_bytes())
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<$name, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                        write!(f, "{} hexadecimal digits or {} bytes", 2 * $name::BYTES, $name::BYTES)
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<$name, E> {
+                        v.parse().map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+                    }
+
+                    fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<$name, E> {
+                        $name::from_bytes(v).map_err(|_| E::invalid_length(v.len(), &self))
+                    }
+
+                    // Formats without a bytes type represent them as sequences
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<$name, A::Error> {
+                        let mut bytes = [0u8; $name::BYTES];
+                        for (i, byte) in bytes.iter_mut().enumerate() {
+                            *byte = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                        }
+                        if seq.next_element::<u8>()?.is_some() {
+                            return Err(serde::de::Error::invalid_length($name::BYTES + 1, &self));
+                        }
+                        Ok($name::from_le_bytes(bytes))
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(Visitor)
+                } else {
+                    deserializer.deserialize_bytes(Visitor)
+                }
+            }
+        }
+    };
+}
+
+digest!(
+    /// A 128-bit hash, as returned by [`gxhash128`], with canonical textual and binary representations, the same
+    /// on every platform, for storing or exchanging hashes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gxhash::GxDigest128;
+    ///
+    /// let digest = GxDigest128::of(b"hello world", 1234);
+    /// assert_eq!(digest.value(), gxhash::gxhash128(b"hello world", 1234));
+    ///
+    /// let hex = digest.to_string();
+    /// assert_eq!(32, hex.len());
+    /// assert_eq!(digest, hex.parse().unwrap());
+    /// assert_eq!(digest, GxDigest128::from_le_bytes(digest.to_le_bytes()));
+    /// ```
+    GxDigest128,
+    u128,
+    gxhash128
+);
+
+digest!(
+    /// A 64-bit hash, as returned by [`gxhash64`], with canonical textual and binary representations, the same
+    /// on every platform, for storing or exchanging hashes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use gxhash::GxDigest64;
+    ///
+    /// let digest = GxDigest64::of(b"hello world", 1234);
+    /// assert_eq!(digest, digest.to_string().parse().unwrap());
+    /// ```
+    GxDigest64,
+    u64,
+    gxhash64
+);
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn display_is_zero_padded_lowercase_hex() {
+        let digest = GxDigest128::from_value(0x0123456789ABCDEF);
+        assert_eq!("00000000000000000123456789abcdef", digest.to_string());
+        assert_eq!(format!("{:032x}", digest.value()), digest.to_string());
+        assert_eq!("GxDigest128(00000000000000000123456789abcdef)", format!("{digest:?}"));
+
+        assert_eq!("000000000000002a", GxDigest64::from_value(42).to_string());
+        assert_eq!("ffffffffffffffff", GxDigest64::from_value(u64::MAX).to_string());
+    }
+
+    #[test]
+    fn display_honors_width_and_alignment() {
+        let digest = GxDigest64::from_value(42);
+        assert_eq!("        000000000000002a", format!("{digest:>24}"));
+        assert_eq!("000000000000002a--------", format!("{digest:-<24}"));
+        assert_eq!("    000000000000002a    ", format!("{digest:^24}"));
+        // Widths narrower than the digest don't truncate it
+        assert_eq!("000000000000002a", format!("{digest:>8}"));
+
+        let digest = GxDigest128::from_value(42);
+        assert_eq!(format!("{:>40}", format!("{:032x}", 42)), format!("{digest:>40}"));
+    }
+
+    #[test]
+    fn parse_roundtrips() {
+        for seed in 0..100 {
+            let digest = GxDigest128::of(b"hello world", seed);
+            assert_eq!(Ok(digest), digest.to_string().parse());
+            assert_eq!(Ok(digest), digest.to_string().to_uppercase().parse());
+
+            let digest = GxDigest64::of(b"hello world", seed);
+            assert_eq!(Ok(digest), digest.to_string().parse());
+        }
+    }
+
+    #[test]
+    fn parse_rejects_non_canonical() {
+        for s in [
+            "",
+            "2a",
+            "+000000000000000000000000000002a",
+            "0x00000000000000000000000000002a",
+            "0000000000000000000000000000002g",
+            "000000000000000000000000000000002a",
+            " 000000000000000000000000000002a",
+        ] {
+            assert_eq!(Err(ParseDigestError(())), s.parse::<GxDigest128>(), "{s} was parsed");
+        }
+        assert_eq!(Err(ParseDigestError(())), "0000000000000000000000000000002a".parse::<GxDigest64>());
+    }
+
+    #[test]
+    fn bytes_are_little_endian() {
+        let digest = GxDigest128::of(b"hello world", 42);
+        assert_eq!(gxhash128(b"hello world", 42).to_le_bytes(), digest.to_le_bytes());
+        assert_eq!(Ok(digest), GxDigest128::from_bytes(&digest.to_le_bytes()));
+        assert_eq!(Err(ParseDigestError(())), GxDigest128::from_bytes(&digest.to_le_bytes()[1..]));
+
+        let digest = GxDigest64::from_value(0x0102030405060708);
+        assert_eq!([8, 7, 6, 5, 4, 3, 2, 1], digest.to_le_bytes());
+        assert_eq!(digest, GxDigest64::from_le_bytes([8, 7, 6, 5, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_uses_canonical_representations() {
+        let digest = GxDigest128::from_value(0x0123456789abcdef);
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!("\"00000000000000000123456789abcdef\"", json);
+        assert_eq!(digest, serde_json::from_str(&json).unwrap());
+        assert!(serde_json::from_str::<GxDigest128>("\"2a\"").is_err());
+
+        let binary = bincode::serialize(&digest).unwrap();
+        assert!(binary.ends_with(&digest.to_le_bytes()));
+        assert_eq!(digest, bincode::deserialize(&binary).unwrap());
+
+        let digest = GxDigest64::of(b"hello world", 0);
+        assert_eq!(digest, serde_json::from_str(&serde_json::to_string(&digest).unwrap()).unwrap());
+        assert_eq!(digest, bincode::deserialize(&bincode::serialize(&digest).unwrap()).unwrap());
+    }
+}
@@ -0,0 +1,442 @@
+// The algorithm itself, written against the functions of a platform module, which must be in scope where it is
+// expanded. It is expanded here against the platform selected at build time, against the x86 platform when the
+// actual platform is to be chosen at runtime (see dispatch.rs), and against the portable platform in tests.
+
+use super::platform::*;
+
+macro_rules! algorithm {
+    () => {
+        use crate::gxhash::page::is_within_page;
+        use crate::gxhash::platform::{KEYS, VECTOR_SIZE};
+
+        #[inline(always)]
+        pub(crate) unsafe fn gxhash(input: &[u8], seed: State) -> State {
+            finalize(aes_encrypt(compress_all(input), seed))
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn compress_all(input: &[u8]) -> State {
+
+            let len = input.len();
+            let mut ptr = input.as_ptr() as *const State;
+
+            if len == 0 {
+                return create_empty();
+            }
+
+            if len <= VECTOR_SIZE {
+                // Input fits on a single SIMD vector, however we might read beyond the input message
+                // Thus we need this safe method that checks if it can safely read beyond or must copy
+                return get_partial(ptr, len);
+            }
+
+            let mut hash_vector: State;
+            let end = ptr as usize + len;
+
+            let extra_bytes_count = len % VECTOR_SIZE;
+            if extra_bytes_count == 0 {
+                crate::gxhash::load_unaligned!(ptr, v0);
+                hash_vector = v0;
+            } else {
+                // If the input length does not match the length of a whole number of SIMD vectors,
+                // it means we'll need to read a partial vector. We can start with the partial vector first,
+                // so that we can safely read beyond since we expect the following bytes to still be part of
+                // the input
+                hash_vector = get_partial_unsafe(ptr, extra_bytes_count);
+                ptr = ptr.cast::<u8>().add(extra_bytes_count).cast();
+            }
+
+            crate::gxhash::load_unaligned!(ptr, v0);
+
+            if len > VECTOR_SIZE * 2 {
+                // Fast path when input length > 32 and <= 48
+                crate::gxhash::load_unaligned!(ptr, v);
+                v0 = aes_encrypt(v0, v);
+
+                if len > VECTOR_SIZE * 3 {
+                    // Fast path when input length > 48 and <= 64
+                    crate::gxhash::load_unaligned!(ptr, v);
+                    v0 = aes_encrypt(v0, v);
+
+                    if len > VECTOR_SIZE * 4 {
+                        // Input message is large and we can use the high ILP loop
+                        hash_vector = compress_many(ptr, end, hash_vector, len);
+                    }
+                }
+            }
+
+            aes_encrypt_last(hash_vector, 
+                aes_encrypt(aes_encrypt(v0, ld(KEYS.as_ptr())), ld(KEYS.as_ptr().offset(4))))
+        }
+
+        #[inline(always)]
+        unsafe fn compress_many(ptr: *const State, end: usize, hash_vector: State, len: usize) -> State {
+
+            let (ptr, hash_vector) = compress_singles(ptr, end, hash_vector);
+
+            // Process the remaining n * 8 blocks
+            // This part may use 128-bit or 256-bit
+            compress_8(ptr, end, hash_vector, len)
+        }
+
+        #[inline(always)]
+        unsafe fn compress_singles(mut ptr: *const State, end: usize, hash_vector: State) -> (*const State, State) {
+
+            const UNROLL_FACTOR: usize = 8;
+
+            let remaining_bytes = end -  ptr as usize;
+
+            let unrollable_blocks_count: usize = remaining_bytes / (VECTOR_SIZE * UNROLL_FACTOR) * UNROLL_FACTOR; 
+
+            let remaining_bytes = remaining_bytes - unrollable_blocks_count * VECTOR_SIZE;
+            let end_address = ptr.add(remaining_bytes / VECTOR_SIZE) as usize;
+
+            // Process first individual blocks until we have an whole number of 8 blocks
+            let mut hash_vector = hash_vector;
+            while (ptr as usize) < end_address {
+                crate::gxhash::load_unaligned!(ptr, v0);
+                hash_vector = aes_encrypt(hash_vector, v0);
+            }
+
+            (ptr, hash_vector)
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn get_partial(p: *const State, len: usize) -> State {
+            // Safety check
+            // Reading beyond the input can't fault when it stays within the page, but it still is an out of bounds read
+            // for tools such as Miri or sanitizers, so it is opted out of with 'safe-reads'.
+            if !cfg!(any(feature = "safe-reads", miri)) && is_within_page(p as *const u8, VECTOR_SIZE) {
+                get_partial_unsafe(p, len)
+            } else {
+                get_partial_safe(p, len)
+            }
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn compress_8(ptr: *const State, end_address: usize, hash_vector: State, len: usize) -> State {
+            let mut lanes = Lanes::new(hash_vector);
+            lanes.compress(ptr, end_address);
+            lanes.merge(len)
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn gxhash_wide(input: &[u8], seed: State) -> (State, State) {
+            let (hash1, hash2) = compress_all_wide(input);
+            finalize_wide(aes_encrypt(hash1, seed), aes_encrypt(hash2, seed))
+        }
+
+        /// Same steps as [`compress_all`], except that the two lanes of [`compress_8`] are kept apart instead of being
+        /// merged, for a 256-bit wide state. Inputs that are too small to go through the lanes have both halves equal.
+        #[inline(always)]
+        unsafe fn compress_all_wide(input: &[u8]) -> (State, State) {
+
+            let len = input.len();
+
+            if len <= VECTOR_SIZE * 4 {
+                let hash_vector = compress_all(input);
+                return (hash_vector, hash_vector);
+            }
+
+            let mut ptr = input.as_ptr() as *const State;
+            let end = ptr as usize + len;
+
+            let hash_vector = match len % VECTOR_SIZE {
+                0 => {
+                    crate::gxhash::load_unaligned!(ptr, v0);
+                    v0
+                }
+                extra_bytes_count => {
+                    let v0 = get_partial_unsafe(ptr, extra_bytes_count);
+                    ptr = ptr.cast::<u8>().add(extra_bytes_count).cast();
+                    v0
+                }
+            };
+
+            crate::gxhash::load_unaligned!(ptr, v0, v1, v2);
+            let v0 = aes_encrypt(aes_encrypt(aes_encrypt(aes_encrypt(v0, v1), v2), ld(KEYS.as_ptr())), ld(KEYS.as_ptr().offset(4)));
+
+            let (ptr, hash_vector) = compress_singles(ptr, end, hash_vector);
+
+            let mut lanes = Lanes::new(hash_vector);
+            lanes.compress(ptr, end);
+            let (lane1, lane2) = lanes.split(len);
+
+            (aes_encrypt_last(lane1, v0), aes_encrypt_last(lane2, v0))
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn finalize(hash: State) -> State {
+            let mut hash = aes_encrypt(hash, ld(KEYS.as_ptr()));
+            hash = aes_encrypt(hash, ld(KEYS.as_ptr().offset(4)));
+            hash = aes_encrypt_last(hash, ld(KEYS.as_ptr().offset(8)));
+
+            hash
+        }
+
+        /// Mixes a vector into the state of a [`GxHasher`](crate::GxHasher)
+        #[inline(always)]
+        pub(crate) unsafe fn mix(state: State, vector: State) -> State {
+            aes_encrypt_last(vector, aes_encrypt(state, ld(KEYS.as_ptr())))
+        }
+
+        /// Combines two hashes into one, in a way that depends on their order. Hashes combined for different purposes
+        /// use different domains, so that they can't be mistaken for one another.
+        #[inline(always)]
+        pub(crate) unsafe fn combine(a: State, b: State, domain: State) -> State {
+            finalize(aes_encrypt(aes_encrypt(a, domain), aes_encrypt(b, domain)))
+        }
+
+        /// Finalizes both halves of a 256-bit state. The halves are first crossed, so that each depends on the whole
+        /// input, then finalized with the keys in opposite orders, so that equal halves still end up different.
+        #[inline(always)]
+        unsafe fn finalize_wide(hash1: State, hash2: State) -> (State, State) {
+            let (hash1, hash2) = (aes_encrypt(hash1, hash2), aes_encrypt(hash2, hash1));
+
+            let mut hash2 = aes_encrypt(hash2, ld(KEYS.as_ptr().offset(8)));
+            hash2 = aes_encrypt(hash2, ld(KEYS.as_ptr().offset(4)));
+            hash2 = aes_encrypt_last(hash2, ld(KEYS.as_ptr()));
+
+            (finalize(hash1), hash2)
+        }
+
+        // Number of independent inputs the batch functions hash at once, enough for the rounds of one input to execute
+        // while the others wait for the results of their previous rounds
+        const BATCH_SIZE: usize = 8;
+
+        #[inline(always)]
+        pub(crate) unsafe fn gxhash_batch(inputs: &[&[u8]], seed: State, out: &mut [u64]) {
+            // Only the path of small inputs is inlined, as copies of the whole of compress_all for each input of a batch
+            // would be slower than hashing them one by one
+            batch(inputs, seed, out, |input| match input.len() {
+                0 => create_empty(),
+                len @ 1..=VECTOR_SIZE => get_partial(input.as_ptr() as *const State, len),
+                _ => compress_all_outlined(input),
+            })
+        }
+
+        #[inline(always)]
+        pub(crate) unsafe fn gxhash_batch_u64(keys: &[u64], seed: State, out: &mut [u64]) {
+            batch(keys, seed, out, |key| {
+                // Same as compress_all on the bytes of the key, without the page check since the buffer is a whole vector
+                let buffer = [key.to_le_bytes(), [0; 8]];
+                get_partial_unsafe(buffer.as_ptr() as *const State, 8)
+            })
+        }
+
+        #[inline(never)]
+        unsafe fn compress_all_outlined(input: &[u8]) -> State {
+            compress_all(input)
+        }
+
+        #[inline(always)]
+        unsafe fn batch<T>(keys: &[T], seed: State, out: &mut [u64], compress: impl Fn(&T) -> State) {
+            let mut keys = keys.chunks_exact(BATCH_SIZE);
+            let mut outs = out.chunks_exact_mut(BATCH_SIZE);
+
+            for (keys, out) in (&mut keys).zip(&mut outs) {
+                let keys: &[T; BATCH_SIZE] = keys.try_into().unwrap_unchecked();
+                let mut hashes = [create_empty(); BATCH_SIZE];
+                for (hash, key) in hashes.iter_mut().zip(keys) {
+                    *hash = aes_encrypt(compress(key), seed);
+                }
+                // Same rounds as finalize, each applied to all hashes before moving on to the next one
+                for hash in hashes.iter_mut() {
+                    *hash = aes_encrypt(*hash, ld(KEYS.as_ptr()));
+                }
+                for hash in hashes.iter_mut() {
+                    *hash = aes_encrypt(*hash, ld(KEYS.as_ptr().offset(4)));
+                }
+                for hash in hashes.iter_mut() {
+                    *hash = aes_encrypt_last(*hash, ld(KEYS.as_ptr().offset(8)));
+                }
+                for (out, hash) in out.iter_mut().zip(hashes) {
+                    *out = u64::from_le(*(&hash as *const State as *const u64));
+                }
+            }
+
+            for (key, out) in keys.remainder().iter().zip(outs.into_remainder()) {
+                let hash = finalize(aes_encrypt(compress(key), seed));
+                *out = u64::from_le(*(&hash as *const State as *const u64));
+            }
+        }
+
+        // Size of the blocks of 8 vectors the lanes compress at once
+        const BLOCK_SIZE: usize = VECTOR_SIZE * 8;
+
+        /// Incremental version of [`compress_all`], for the [`GxStreamHasher`](crate::GxStreamHasher), for an input which length is known upfront.
+        /// The input goes through the same steps, in the same order:
+        /// - The leading partial (or full) vector, which becomes the hash vector
+        /// - The next 3 vectors, compressed on their own
+        /// - The individual vectors before the input is down to a whole number of 8 vectors blocks
+        /// - The 8 vectors blocks, compressed in two lanes
+        ///
+        /// Steps are carried out once all of their bytes are available, which takes at most a block of buffering.
+        /// The layout is the same whatever the platform, so that blocks can go from one platform to the other when the
+        /// platform is chosen at runtime.
+        #[repr(C)]
+        #[derive(Clone, Debug)]
+        pub(crate) struct Blocks {
+            len: usize,
+            // Offsets at which each step ends
+            head_end: usize,
+            v0_end: usize,
+            singles_end: usize,
+            processed: usize,
+            buffered: usize,
+            buffer: [u8; BLOCK_SIZE],
+            hash_vector: State,
+            v0: State,
+            lanes: Lanes,
+        }
+
+        impl Blocks {
+            #[inline]
+            pub(crate) fn new(len: usize) -> Blocks {
+                let head_end = match len % VECTOR_SIZE {
+                    0 => VECTOR_SIZE,
+                    extra_bytes_count => extra_bytes_count,
+                };
+                let v0_end = head_end + VECTOR_SIZE * 3;
+                let singles_end = v0_end + len.saturating_sub(v0_end) % BLOCK_SIZE;
+
+                unsafe {
+                    Blocks {
+                        len,
+                        head_end,
+                        v0_end,
+                        singles_end,
+                        processed: 0,
+                        buffered: 0,
+                        buffer: [0; BLOCK_SIZE],
+                        hash_vector: create_empty(),
+                        v0: create_empty(),
+                        lanes: Lanes::new(create_empty()),
+                    }
+                }
+            }
+
+            /// Small inputs don't go through the high ILP loop, they are simply buffered and handed to [`compress_all`]
+            #[inline]
+            fn is_small(&self) -> bool {
+                self.len <= VECTOR_SIZE * 4
+            }
+
+            #[inline(always)]
+            unsafe fn write(&mut self, mut bytes: &[u8]) {
+                assert!(
+                    self.processed + self.buffered + bytes.len() <= self.len,
+                    "more bytes written than the announced length of {}",
+                    self.len
+                );
+
+                if self.is_small() {
+                    self.buffer[self.buffered..self.buffered + bytes.len()].copy_from_slice(bytes);
+                    self.buffered += bytes.len();
+                    return;
+                }
+
+                while !bytes.is_empty() {
+                    if self.buffered == 0 && self.processed >= self.singles_end {
+                        // Compress whole blocks straight from the input
+                        let blocks_len = bytes.len() / BLOCK_SIZE * BLOCK_SIZE;
+                        if blocks_len > 0 {
+                            let ptr = bytes.as_ptr() as *const State;
+                            self.lanes.compress(ptr, ptr as usize + blocks_len);
+                            self.processed += blocks_len;
+                            bytes = &bytes[blocks_len..];
+                            continue;
+                        }
+                    }
+
+                    let step_len = self.step_end() - self.processed;
+                    let count = (step_len - self.buffered).min(bytes.len());
+                    self.buffer[self.buffered..self.buffered + count].copy_from_slice(&bytes[..count]);
+                    self.buffered += count;
+                    bytes = &bytes[count..];
+
+                    if self.buffered == step_len {
+                        self.process_step();
+                        self.processed += step_len;
+                        self.buffered = 0;
+                        if self.processed == self.singles_end {
+                            self.lanes = Lanes::new(self.hash_vector);
+                        }
+                    }
+                }
+            }
+
+            #[inline]
+            fn step_end(&self) -> usize {
+                if self.processed < self.head_end {
+                    self.head_end
+                } else if self.processed < self.v0_end {
+                    self.v0_end
+                } else if self.processed < self.singles_end {
+                    self.singles_end
+                } else {
+                    self.processed + BLOCK_SIZE
+                }
+            }
+
+            #[inline(always)]
+            unsafe fn process_step(&mut self) {
+                let mut ptr = self.buffer.as_ptr() as *const State;
+                if self.processed < self.head_end {
+                    // The buffer is always larger than a vector, so the partial vector can be read with no checks
+                    self.hash_vector = match self.head_end {
+                        VECTOR_SIZE => load_unaligned(ptr),
+                        extra_bytes_count => get_partial_unsafe(ptr, extra_bytes_count),
+                    };
+                } else if self.processed < self.v0_end {
+                    self.v0 = aes_encrypt(aes_encrypt(load_unaligned(ptr), load_unaligned(ptr.add(1))), load_unaligned(ptr.add(2)));
+                } else if self.processed < self.singles_end {
+                    let end_address = ptr as usize + self.buffered;
+                    while (ptr as usize) < end_address {
+                        crate::gxhash::load_unaligned!(ptr, v0);
+                        self.hash_vector = aes_encrypt(self.hash_vector, v0);
+                    }
+                } else {
+                    self.lanes.compress(ptr, ptr as usize + BLOCK_SIZE);
+                }
+            }
+
+            /// Whether all of the `len` bytes were written
+            #[inline]
+            pub(crate) fn is_complete(&self) -> bool {
+                self.processed + self.buffered == self.len
+            }
+
+            // Incomplete inputs give a hash that is unspecified but safe to compute, callers check for completeness
+            #[inline(always)]
+            unsafe fn compress(&self) -> State {
+                if self.is_small() {
+                    return compress_all(&self.buffer[..self.len]);
+                }
+
+                aes_encrypt_last(
+                    self.lanes.merge(self.len),
+                    aes_encrypt(aes_encrypt(self.v0, ld(KEYS.as_ptr())), ld(KEYS.as_ptr().offset(4))),
+                )
+            }
+        }
+
+        /// Writes the next bytes of a stream into its blocks
+        #[inline(always)]
+        pub(crate) unsafe fn stream_write(blocks: &mut Blocks, bytes: &[u8]) {
+            blocks.write(bytes)
+        }
+
+        /// Hash of a stream which bytes were all written into its blocks, as [`gxhash`] of the whole of them.
+        /// The hash of a stream that wasn't written in full is unspecified.
+        #[inline(always)]
+        pub(crate) unsafe fn stream_digest(blocks: &Blocks, seed: State) -> State {
+            finalize(aes_encrypt(blocks.compress(), seed))
+        }
+    };
+}
+
+#[cfg(any(test, all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes"))))]
+pub(crate) use algorithm;
+
+algorithm!();
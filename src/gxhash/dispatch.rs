@@ -0,0 +1,262 @@
+// Runtime selection of the platform, for x86 builds that don't target the 'aes' feature.
+// Such builds use the portable platform, unless the CPU turns out to support AES-NI, in which case the
+// x86 platform below is used instead. The choice is made once, on first use, and cached as a table of
+// function pointers for the entry points of the hash functions and the hasher.
+// Both platforms have states of the same size and layout, so states can freely go from one to the other.
+// The same goes for the blocks of the streaming hasher, which are made of states and bytes only.
+
+use std::mem::transmute;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::gxhash::platform::{State, KEYS, VECTOR_SIZE};
+use crate::gxhash::Blocks;
+
+// The x86 platform and the algorithm on top of it, compiled whatever the target features of the build are.
+// These must only be called from functions enabling the 'aes' feature, so that intrinsics can be inlined.
+#[allow(dead_code)]
+#[path = "platform/x86.rs"]
+mod platform;
+#[allow(dead_code)]
+mod algorithm {
+    use super::platform::*;
+    crate::gxhash::algorithm::algorithm!();
+}
+
+const _: () = assert!(core::mem::size_of::<Blocks>() == core::mem::size_of::<algorithm::Blocks>());
+const _: () = assert!(core::mem::align_of::<Blocks>() == core::mem::align_of::<algorithm::Blocks>());
+
+struct Functions {
+    backend: &'static str,
+    gxhash: unsafe fn(&[u8], State) -> State,
+    gxhash_wide: unsafe fn(&[u8], State) -> (State, State),
+    gxhash_batch: unsafe fn(&[&[u8]], State, &mut [u64]),
+    gxhash_batch_u64: unsafe fn(&[u64], State, &mut [u64]),
+    compress_all: unsafe fn(&[u8]) -> State,
+    finalize: unsafe fn(State) -> State,
+    mix: unsafe fn(State, State) -> State,
+    combine: unsafe fn(State, State, State) -> State,
+    stream_write: unsafe fn(&mut Blocks, &[u8]),
+    stream_digest: unsafe fn(&Blocks, State) -> State,
+}
+
+static PORTABLE: Functions = Functions {
+    backend: super::platform::BACKEND,
+    gxhash: super::algorithm::gxhash,
+    gxhash_wide: super::algorithm::gxhash_wide,
+    gxhash_batch: super::algorithm::gxhash_batch,
+    gxhash_batch_u64: super::algorithm::gxhash_batch_u64,
+    compress_all: super::algorithm::compress_all,
+    finalize: super::algorithm::finalize,
+    mix: super::algorithm::mix,
+    combine: super::algorithm::combine,
+    stream_write: super::algorithm::stream_write,
+    stream_digest: super::algorithm::stream_digest,
+};
+
+static AES: Functions = Functions {
+    backend: platform::BACKEND,
+    gxhash: aes_gxhash,
+    gxhash_wide: aes_gxhash_wide,
+    gxhash_batch: aes_gxhash_batch,
+    gxhash_batch_u64: aes_gxhash_batch_u64,
+    compress_all: aes_compress_all,
+    finalize: aes_finalize,
+    mix: aes_mix,
+    combine: aes_combine,
+    stream_write: aes_stream_write,
+    stream_digest: aes_stream_digest,
+};
+
+static FUNCTIONS: AtomicPtr<Functions> = AtomicPtr::new(std::ptr::null_mut());
+
+#[inline(always)]
+fn functions() -> &'static Functions {
+    let functions = FUNCTIONS.load(Ordering::Relaxed);
+    if functions.is_null() {
+        detect()
+    } else {
+        unsafe { &*functions }
+    }
+}
+
+#[cold]
+fn detect() -> &'static Functions {
+    let functions = if is_x86_feature_detected!("aes") && is_x86_feature_detected!("sse2") { &AES } else { &PORTABLE };
+    FUNCTIONS.store(functions as *const Functions as *mut Functions, Ordering::Relaxed);
+    functions
+}
+
+#[inline(always)]
+pub(crate) fn backend() -> &'static str {
+    functions().backend
+}
+
+#[inline(always)]
+pub(crate) unsafe fn gxhash(input: &[u8], seed: State) -> State {
+    (functions().gxhash)(input, seed)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn gxhash_wide(input: &[u8], seed: State) -> (State, State) {
+    (functions().gxhash_wide)(input, seed)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn gxhash_batch(inputs: &[&[u8]], seed: State, out: &mut [u64]) {
+    (functions().gxhash_batch)(inputs, seed, out)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn gxhash_batch_u64(keys: &[u64], seed: State, out: &mut [u64]) {
+    (functions().gxhash_batch_u64)(keys, seed, out)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn compress_all(input: &[u8]) -> State {
+    (functions().compress_all)(input)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn finalize(hash: State) -> State {
+    (functions().finalize)(hash)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn mix(state: State, vector: State) -> State {
+    (functions().mix)(state, vector)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn combine(a: State, b: State, domain: State) -> State {
+    (functions().combine)(a, b, domain)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn stream_write(blocks: &mut Blocks, bytes: &[u8]) {
+    (functions().stream_write)(blocks, bytes)
+}
+
+#[inline(always)]
+pub(crate) unsafe fn stream_digest(blocks: &Blocks, seed: State) -> State {
+    (functions().stream_digest)(blocks, seed)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_gxhash(input: &[u8], seed: State) -> State {
+    transmute(algorithm::gxhash(input, transmute::<State, platform::State>(seed)))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_gxhash_wide(input: &[u8], seed: State) -> (State, State) {
+    let (hash1, hash2) = algorithm::gxhash_wide(input, transmute::<State, platform::State>(seed));
+    (transmute::<platform::State, State>(hash1), transmute::<platform::State, State>(hash2))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_gxhash_batch(inputs: &[&[u8]], seed: State, out: &mut [u64]) {
+    algorithm::gxhash_batch(inputs, transmute::<State, platform::State>(seed), out)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_gxhash_batch_u64(keys: &[u64], seed: State, out: &mut [u64]) {
+    algorithm::gxhash_batch_u64(keys, transmute::<State, platform::State>(seed), out)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_compress_all(input: &[u8]) -> State {
+    transmute(algorithm::compress_all(input))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_finalize(hash: State) -> State {
+    transmute(algorithm::finalize(transmute::<State, platform::State>(hash)))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_mix(state: State, vector: State) -> State {
+    transmute(algorithm::mix(transmute::<State, platform::State>(state), transmute::<State, platform::State>(vector)))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_combine(a: State, b: State, domain: State) -> State {
+    transmute(algorithm::combine(
+        transmute::<State, platform::State>(a),
+        transmute::<State, platform::State>(b),
+        transmute::<State, platform::State>(domain),
+    ))
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_stream_write(blocks: &mut Blocks, bytes: &[u8]) {
+    algorithm::stream_write(&mut *(blocks as *mut Blocks as *mut algorithm::Blocks), bytes)
+}
+
+#[target_feature(enable = "aes,sse2")]
+unsafe fn aes_stream_digest(blocks: &Blocks, seed: State) -> State {
+    transmute(algorithm::stream_digest(&*(blocks as *const Blocks as *const algorithm::Blocks), transmute::<State, platform::State>(seed)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::Rng;
+
+    use super::*;
+
+    fn bytes(state: State) -> [u8; VECTOR_SIZE] {
+        unsafe { transmute(state) }
+    }
+
+    fn random_state(rng: &mut impl Rng) -> State {
+        unsafe { crate::gxhash::platform::load_u128(rng.gen()) }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn platforms_are_interchangeable() {
+        if !is_x86_feature_detected!("aes") {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut input = [0u8; 1200];
+        rng.fill(input.as_mut_slice());
+
+        for len in 0..input.len() {
+            let (seed, state) = (random_state(&mut rng), random_state(&mut rng));
+            unsafe {
+                assert_eq!(bytes((PORTABLE.gxhash)(&input[..len], seed)), bytes((AES.gxhash)(&input[..len], seed)));
+                let (wide, aes_wide) = ((PORTABLE.gxhash_wide)(&input[..len], seed), (AES.gxhash_wide)(&input[..len], seed));
+                assert_eq!((bytes(wide.0), bytes(wide.1)), (bytes(aes_wide.0), bytes(aes_wide.1)));
+                let inputs = [&input[..len], &input[len / 2..len], &input[..len / 3], &input[len / 4..], &[]];
+                let (mut hashes, mut aes_hashes) = ([0u64; 5], [0u64; 5]);
+                (PORTABLE.gxhash_batch)(&inputs, seed, &mut hashes);
+                (AES.gxhash_batch)(&inputs, seed, &mut aes_hashes);
+                assert_eq!(hashes, aes_hashes);
+                let keys = [len as u64, !(len as u64), 0, u64::MAX, 42];
+                (PORTABLE.gxhash_batch_u64)(&keys, seed, &mut hashes);
+                (AES.gxhash_batch_u64)(&keys, seed, &mut aes_hashes);
+                assert_eq!(hashes, aes_hashes);
+                assert_eq!(bytes((PORTABLE.compress_all)(&input[..len])), bytes((AES.compress_all)(&input[..len])));
+                assert_eq!(bytes((PORTABLE.finalize)(state)), bytes((AES.finalize)(state)));
+                assert_eq!(bytes((PORTABLE.mix)(state, seed)), bytes((AES.mix)(state, seed)));
+                assert_eq!(bytes((PORTABLE.combine)(state, seed, state)), bytes((AES.combine)(state, seed, state)));
+                let (mut blocks, mut aes_blocks) = (Blocks::new(len), Blocks::new(len));
+                for chunk in input[..len].chunks(len / 3 + 1) {
+                    (PORTABLE.stream_write)(&mut blocks, chunk);
+                    (AES.stream_write)(&mut aes_blocks, chunk);
+                }
+                let hash = bytes((AES.stream_digest)(&aes_blocks, seed));
+                assert_eq!(bytes((PORTABLE.stream_digest)(&blocks, seed)), hash);
+                assert_eq!(bytes((AES.gxhash)(&input[..len], seed)), hash);
+            }
+        }
+    }
+
+    #[test]
+    fn aes_is_used_when_available() {
+        let expected = if is_x86_feature_detected!("aes") { &AES } else { &PORTABLE };
+        assert!(std::ptr::eq(functions(), expected));
+        assert_eq!(expected.backend, crate::backend());
+    }
+}
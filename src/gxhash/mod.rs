@@ -1,6 +1,26 @@
+// The hashes computed here are also the ones of `crate::v3`, which re-exports them: changing them requires copying
+// the current algorithm into that module first (see src/v3.rs).
 pub(crate) mod platform;
+mod algorithm;
+mod page;
+#[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes")))]
+mod dispatch;
 
 use platform::*;
+#[cfg(not(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes"))))]
+pub(crate) use algorithm::{combine, compress_all, finalize, gxhash, gxhash_batch, gxhash_batch_u64, gxhash_wide, mix, stream_digest, stream_write};
+#[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes")))]
+pub(crate) use dispatch::{combine, compress_all, finalize, gxhash, gxhash_batch, gxhash_batch_u64, gxhash_wide, mix, stream_digest, stream_write};
+pub(crate) use algorithm::Blocks;
+
+// The portable platform and the algorithm on top of it, whatever the platform of the build, for tests checking
+// them against the platform of the build and against the published vectors
+#[cfg(test)]
+#[allow(dead_code)]
+pub(crate) mod portable {
+    pub(crate) use super::platform::portable::*;
+    super::algorithm::algorithm!();
+}
 
 /// Hashes an arbitrary stream of bytes to an u32.
 ///
@@ -15,7 +35,7 @@ use platform::*;
 pub fn gxhash32(input: &[u8], seed: i64) -> u32 {
     unsafe {
         let p = &gxhash(input, create_seed(seed)) as *const State as *const u32;
-        *p
+        u32::from_le(*p)
     }
 }
 
@@ -32,7 +52,7 @@ pub fn gxhash32(input: &[u8], seed: i64) -> u32 {
 pub fn gxhash64(input: &[u8], seed: i64) -> u64 {
     unsafe {
         let p = &gxhash(input, create_seed(seed)) as *const State as *const u64;
-        *p
+        u64::from_le(*p)
     }
 }
 
@@ -49,106 +69,142 @@ pub fn gxhash64(input: &[u8], seed: i64) -> u64 {
 pub fn gxhash128(input: &[u8], seed: i64) -> u128 {
     unsafe {
         let p = &gxhash(input, create_seed(seed)) as *const State as *const u128;
-        *p
+        u128::from_le(*p)
     }
 }
 
-macro_rules! load_unaligned {
-    ($ptr:ident, $($var:ident),+) => {
-        $(
-            #[allow(unused_mut)]
-            let mut $var = load_unaligned($ptr);
-            $ptr = ($ptr).offset(1);
-        )+
-    };
+/// Hashes each of the given inputs to an u64, writing the hashes to `out`. Hashes are the same as the ones
+/// of [`gxhash64`], but hashing many small inputs at once is faster, as the AES rounds of several inputs are
+/// interleaved instead of waiting for one another.
+///
+/// # Panics
+/// Panics if `inputs` and `out` have different lengths.
+///
+/// # Example
+///
+/// ```
+/// let inputs: [&[u8]; 3] = [b"hello", b"world", b"!"];
+/// let mut hashes = [0u64; 3];
+/// gxhash::gxhash64_batch(&inputs, 1234, &mut hashes);
+/// assert_eq!(hashes[1], gxhash::gxhash64(b"world", 1234));
+/// ```
+#[inline]
+pub fn gxhash64_batch(inputs: &[&[u8]], seed: i64, out: &mut [u64]) {
+    assert_eq!(inputs.len(), out.len(), "there must be as many outputs as inputs");
+    unsafe { gxhash_batch(inputs, create_seed(seed), out) }
 }
 
-pub(crate) use load_unaligned;
-
-#[inline(always)]
-pub(crate) unsafe fn gxhash(input: &[u8], seed: State) -> State {
-    finalize(aes_encrypt(compress_all(input), seed))
+/// Hashes each of the given keys to an u64, writing the hashes to `out`. Hashes are the same as the ones
+/// of [`gxhash64`] on the little endian bytes of the keys, computed as with [`gxhash64_batch`].
+///
+/// # Panics
+/// Panics if `keys` and `out` have different lengths.
+///
+/// # Example
+///
+/// ```
+/// let keys = [1u64, 2, 3];
+/// let mut hashes = [0u64; 3];
+/// gxhash::gxhash64_batch_u64(&keys, 1234, &mut hashes);
+/// assert_eq!(hashes[2], gxhash::gxhash64(&3u64.to_le_bytes(), 1234));
+/// ```
+#[inline]
+pub fn gxhash64_batch_u64(keys: &[u64], seed: i64, out: &mut [u64]) {
+    assert_eq!(keys.len(), out.len(), "there must be as many outputs as keys");
+    unsafe { gxhash_batch_u64(keys, create_seed(seed), out) }
 }
 
+/// Hashes an arbitrary stream of bytes to 256 bits, for uses where the collision probability of 128-bit hashes
+/// is too high, such as fingerprinting very large numbers of inputs.
+/// Inputs over 4 vectors (64 bytes) are compressed into a 256-bit state, while smaller inputs are compressed into
+/// a 128-bit state, which is then expanded to 256 bits.
+///
+/// # Example
+///
+/// ```
+/// let bytes = [42u8; 1000];
+/// let seed = 1234;
+/// println!("Hash is {:x?}!", gxhash::gxhash256(&bytes, seed));
+/// ```
 #[inline(always)]
-pub(crate) unsafe fn compress_all(input: &[u8]) -> State {
-
-    let len = input.len();
-    let mut ptr = input.as_ptr() as *const State;
-
-    if len == 0 {
-        return create_empty();
-    }
-
-    if len <= VECTOR_SIZE {
-        // Input fits on a single SIMD vector, however we might read beyond the input message
-        // Thus we need this safe method that checks if it can safely read beyond or must copy
-        return get_partial(ptr, len);
-    }
-
-    let mut hash_vector: State;
-    let end = ptr as usize + len;
-
-    let extra_bytes_count = len % VECTOR_SIZE;
-    if extra_bytes_count == 0 {
-        load_unaligned!(ptr, v0);
-        hash_vector = v0;
-    } else {
-        // If the input length does not match the length of a whole number of SIMD vectors,
-        // it means we'll need to read a partial vector. We can start with the partial vector first,
-        // so that we can safely read beyond since we expect the following bytes to still be part of
-        // the input
-        hash_vector = get_partial_unsafe(ptr, extra_bytes_count);
-        ptr = ptr.cast::<u8>().add(extra_bytes_count).cast();
+pub fn gxhash256(input: &[u8], seed: i64) -> [u8; 32] {
+    unsafe {
+        let (hash1, hash2) = gxhash_wide(input, create_seed(seed));
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&*(&hash1 as *const State as *const [u8; 16]));
+        bytes[16..].copy_from_slice(&*(&hash2 as *const State as *const [u8; 16]));
+        bytes
     }
+}
 
-    load_unaligned!(ptr, v0);
-
-    if len > VECTOR_SIZE * 2 {
-        // Fast path when input length > 32 and <= 48
-        load_unaligned!(ptr, v);
-        v0 = aes_encrypt(v0, v);
-
-        if len > VECTOR_SIZE * 3 {
-            // Fast path when input length > 48 and <= 64
-            load_unaligned!(ptr, v);
-            v0 = aes_encrypt(v0, v);
-
-            if len > VECTOR_SIZE * 4 {
-                // Input message is large and we can use the high ILP loop
-                hash_vector = compress_many(ptr, end, hash_vector, len);
-            }
-        }
+/// Hashes an arbitrary stream of bytes to an u64, using the provided 128-bit key.
+/// Unlike the 64-bit seed of [`gxhash64`], which is repeated over both halves of the internal state,
+/// the key is used as the whole state, for a key space of 128 bits.
+///
+/// # Example
+///
+/// ```
+/// let bytes = [42u8; 1000];
+/// let key = [7u8; 16];
+/// println!("Hash is {:x}!", gxhash::gxhash64_with_key(&bytes, &key));
+/// ```
+#[inline(always)]
+pub fn gxhash64_with_key(input: &[u8], key: &[u8; 16]) -> u64 {
+    unsafe {
+        let p = &gxhash(input, create_key(key)) as *const State as *const u64;
+        u64::from_le(*p)
     }
-    
-    return aes_encrypt_last(hash_vector, 
-        aes_encrypt(aes_encrypt(v0, ld(KEYS.as_ptr())), ld(KEYS.as_ptr().offset(4))));
 }
 
+/// Hashes an arbitrary stream of bytes to an u128, using the provided 128-bit key.
+/// Unlike the 64-bit seed of [`gxhash128`], which is repeated over both halves of the internal state,
+/// the key is used as the whole state, for a key space of 128 bits.
+///
+/// # Example
+///
+/// ```
+/// let bytes = [42u8; 1000];
+/// let key = [7u8; 16];
+/// println!("Hash is {:x}!", gxhash::gxhash128_with_key(&bytes, &key));
+/// ```
 #[inline(always)]
-unsafe fn compress_many(mut ptr: *const State, end: usize, hash_vector: State, len: usize) -> State {
-
-    const UNROLL_FACTOR: usize = 8;
-
-    let remaining_bytes = end -  ptr as usize;
-
-    let unrollable_blocks_count: usize = remaining_bytes / (VECTOR_SIZE * UNROLL_FACTOR) * UNROLL_FACTOR; 
-
-    let remaining_bytes = remaining_bytes - unrollable_blocks_count * VECTOR_SIZE;
-    let end_address = ptr.add(remaining_bytes / VECTOR_SIZE) as usize;
-
-    // Process first individual blocks until we have an whole number of 8 blocks
-    let mut hash_vector = hash_vector;
-    while (ptr as usize) < end_address {
-        load_unaligned!(ptr, v0);
-        hash_vector = aes_encrypt(hash_vector, v0);
+pub fn gxhash128_with_key(input: &[u8], key: &[u8; 16]) -> u128 {
+    unsafe {
+        let p = &gxhash(input, create_key(key)) as *const State as *const u128;
+        u128::from_le(*p)
     }
+}
+
+/// Name of the implementation hashes are computed with, for diagnostics: `"x86-aes"` with AES-NI, `"x86-vaes"`
+/// when also using VAES and AVX2 for large inputs, `"arm-aes"` with the ARM cryptography extensions, or
+/// `"portable"` otherwise. With the `runtime-dispatch` feature, this is the implementation selected for the
+/// current CPU. Hashes are the same whatever the implementation.
+///
+/// # Example
+///
+/// ```
+/// println!("Hashing with {}", gxhash::backend());
+/// ```
+#[inline]
+pub fn backend() -> &'static str {
+    #[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes")))]
+    return dispatch::backend();
+    #[cfg(not(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(target_feature = "aes"))))]
+    return BACKEND;
+}
 
-    // Process the remaining n * 8 blocks
-    // This part may use 128-bit or 256-bit
-    compress_8(ptr, end, hash_vector, len)
+macro_rules! load_unaligned {
+    ($ptr:ident, $($var:ident),+) => {
+        $(
+            #[allow(unused_mut)]
+            let mut $var = load_unaligned($ptr);
+            $ptr = ($ptr).offset(1);
+        )+
+    };
 }
 
+pub(crate) use load_unaligned;
+
 #[cfg(test)]
 mod tests {
 
@@ -156,6 +212,7 @@ mod tests {
     use rand::Rng;
 
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn all_blocks_are_consumed() {
         for s in 1..1200 {
             let mut bytes = vec![42u8; s];
@@ -173,6 +230,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::unnecessary_mut_passed)]
     fn add_zeroes_mutates_hash() {
         let mut bytes = [0u8; 1200];
 
@@ -189,6 +247,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg_attr(miri, ignore)]
     fn does_not_hash_outside_of_bounds() {
         let mut bytes = [0u8; 1200];
         const OFFSET: usize = 100;
@@ -206,6 +265,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn does_not_read_beyond_input() {
+        let mut padded = [0u8; 100];
+        rand::thread_rng().fill(&mut padded[..]);
+
+        for len in 0..=padded.len() {
+            // Under Miri (or with 'safe-reads'), reading beyond the exactly sized allocation would be reported
+            let exact: Box<[u8]> = padded[..len].into();
+            assert_eq!(gxhash128(&padded[..len], 42), gxhash128(&exact, 42), "hash differs for input of size {len}");
+
+            let mut hashes = [0u64; 1];
+            gxhash64_batch(&[&exact], 42, &mut hashes);
+            assert_eq!(gxhash64(&padded[..len], 42), hashes[0]);
+        }
+    }
+
     #[test]
     fn hash_of_zero_is_not_zero() {
         assert_ne!(0, gxhash32(&[0u8; 0], 0));
@@ -213,11 +288,132 @@ mod tests {
         assert_ne!(0, gxhash32(&[0u8; 1200], 0));
     }
 
+    #[test]
+    fn seed_is_a_repeated_key() {
+        let seed = 0x0123456789ABCDEFi64;
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&seed.to_le_bytes());
+        key[8..].copy_from_slice(&seed.to_le_bytes());
+
+        for len in [0, 1, 16, 17, 100, 1000] {
+            let bytes = vec![42u8; len];
+            assert_eq!(gxhash64(&bytes, seed), gxhash64_with_key(&bytes, &key));
+            assert_eq!(gxhash128(&bytes, seed), gxhash128_with_key(&bytes, &key));
+        }
+    }
+
+    #[test]
+    fn every_key_byte_mutates_hash() {
+        let bytes = [42u8; 100];
+        let ref_hash = gxhash128_with_key(&bytes, &[0u8; 16]);
+
+        for i in 0..16 {
+            let mut key = [0u8; 16];
+            key[i] = 1;
+            assert_ne!(ref_hash, gxhash128_with_key(&bytes, &key), "key byte {i} not used");
+        }
+    }
+
     #[test]
     fn is_stable() {
         assert_eq!(2533353535, gxhash32(&[0u8; 0], 0));
         assert_eq!(4243413987, gxhash32(&[0u8; 1], 0));
         assert_eq!(2401749549, gxhash32(&[0u8; 1000], 0));
         assert_eq!(4156851105, gxhash32(&[42u8; 4242], 42));
+
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        assert_eq!(358584452905803871, gxhash64_with_key(&[42u8; 1000], &key));
+        assert_eq!(102505012870103564430266063287501086153, gxhash128_with_key(&[0u8; 0], &key));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn all_blocks_are_consumed_256() {
+        for s in 1..600 {
+            let mut bytes = vec![42u8; s];
+            let ref_hash = gxhash256(&bytes, 0);
+
+            for i in 0..bytes.len() {
+                let swap = bytes[i];
+                bytes[i] = 82;
+                let new_hash = gxhash256(&bytes, 0);
+                bytes[i] = swap;
+
+                // Both halves must depend on every byte, whatever the lane it went through
+                assert_ne!(ref_hash[..16], new_hash[..16], "byte {i} not processed in first half for input of size {s}");
+                assert_ne!(ref_hash[16..], new_hash[16..], "byte {i} not processed in second half for input of size {s}");
+            }
+        }
+    }
+
+    #[test]
+    fn halves_of_256_differ() {
+        for s in [0, 1, 16, 64, 65, 1000] {
+            let hash = gxhash256(&vec![0u8; s], 0);
+            assert_ne!(hash[..16], hash[16..], "same halves for input of size {s}");
+        }
+    }
+
+    #[test]
+    fn is_stable_256() {
+        fn hex(hash: [u8; 32]) -> String {
+            hash.iter().map(|byte| format!("{byte:02x}")).collect()
+        }
+
+        assert_eq!("bbda5cde5abfcd1afb196fcdba2197dbf416e3d31f56c2e469260024229cca2f", hex(gxhash256(&[0u8; 0], 0)));
+        assert_eq!("106063b067ce64c4e28d9aa6e63d8c5a8e2681d77aec39b5382b6d35944aa468", hex(gxhash256(&[0u8; 1], 0)));
+        assert_eq!("d9fb18f0f37456452b8a65c8937304634e4c83cc6e7f5f3e977ec74daf1d01e0", hex(gxhash256(&[0u8; 1000], 0)));
+        assert_eq!("58a4eaa8d2b7ed079b2f07136c1b057e5b8d5addcaae5d944069b9b5f67130fc", hex(gxhash256(&[42u8; 4242], 42)));
+    }
+
+    #[test]
+    fn batch_matches_gxhash64() {
+        let mut rng = rand::thread_rng();
+        let mut bytes = [0u8; 1200];
+        rng.fill(bytes.as_mut_slice());
+
+        // Every batch size, so that both the interleaved chunks and the remainder are covered
+        for count in 0..40 {
+            // Starts and lengths both below half of the bytes, so that every input is within them
+            let inputs: Vec<&[u8]> = (0..count).map(|i| (i * 13 % 600, i * 29 % 600)).map(|(start, len)| &bytes[start..start + len]).collect();
+            let keys: Vec<u64> = (0..count).map(|_| rng.gen()).collect();
+            let seed = rng.gen::<i64>();
+
+            let mut hashes = vec![0u64; count];
+            gxhash64_batch(&inputs, seed, &mut hashes);
+            for (input, hash) in inputs.iter().zip(&hashes) {
+                assert_eq!(gxhash64(input, seed), *hash, "batch hash differs for input of size {}", input.len());
+            }
+
+            gxhash64_batch_u64(&keys, seed, &mut hashes);
+            for (key, hash) in keys.iter().zip(&hashes) {
+                assert_eq!(gxhash64(&key.to_le_bytes(), seed), *hash, "batch hash differs for key {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn batch_hashes_empty_inputs() {
+        let mut hashes = [0u64; 9];
+        gxhash64_batch(&[&[][..]; 9], 42, &mut hashes);
+        assert_eq!([gxhash64(&[], 42); 9], hashes);
+    }
+
+    #[test]
+    #[should_panic]
+    fn batch_rejects_mismatched_output() {
+        gxhash64_batch_u64(&[1, 2, 3], 0, &mut [0u64; 2]);
+    }
+
+    #[test]
+    fn backend_matches_target() {
+        let backend = backend();
+        if cfg!(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", target_feature = "sse2")) {
+            assert!(backend == "x86-aes" || backend == "x86-vaes");
+        } else if cfg!(all(any(target_arch = "arm", target_arch = "aarch64"), target_feature = "aes", target_feature = "neon")) {
+            assert_eq!("arm-aes", backend);
+        } else if !cfg!(feature = "runtime-dispatch") {
+            assert_eq!("portable", backend);
+        }
     }
 }
@@ -0,0 +1,144 @@
+// Partial vectors may be read beyond the end of the input, as long as the read doesn't cross a page boundary.
+// Memory protection applies to whole pages, so such reads can't fault, provided that actual pages are at
+// least as large as the page size assumed here.
+// 4KiB is the smallest page size of the supported targets, and is conservative for others, such as MacOS ARM
+// (16KiB) or Linux kernels with 64KiB pages. Builds that only target larger pages can set the GXHASH_PAGE_SIZE
+// environment variable to their page size, for fewer fallbacks to copying partial vectors.
+// The assumption is checked against the actual page size by the tests on Linux, once rather than on every read.
+
+pub(crate) const PAGE_SIZE: usize = match option_env!("GXHASH_PAGE_SIZE") {
+    // Validated by the build script
+    Some(size) => parse(size),
+    None => 0x1000,
+};
+
+const fn parse(digits: &str) -> usize {
+    let digits = digits.as_bytes();
+    let mut value = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        value = value * 10 + (digits[i] - b'0') as usize;
+        i += 1;
+    }
+    value
+}
+
+/// Whether the `len` bytes starting at `ptr` are all within the same page
+#[inline(always)]
+pub(crate) fn is_within_page(ptr: *const u8, len: usize) -> bool {
+    let offset_within_page = ptr as usize & (PAGE_SIZE - 1);
+    offset_within_page <= PAGE_SIZE - len
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn page_size_is_a_power_of_two() {
+        assert!(PAGE_SIZE.is_power_of_two());
+        assert_eq!(PAGE_SIZE, parse(&PAGE_SIZE.to_string()));
+    }
+
+    #[test]
+    fn reads_within_page_are_detected() {
+        let page = std::ptr::null::<u8>().wrapping_add(PAGE_SIZE * 42);
+        assert!(is_within_page(page, 16));
+        assert!(is_within_page(page.wrapping_add(PAGE_SIZE - 16), 16));
+        assert!(!is_within_page(page.wrapping_add(PAGE_SIZE - 15), 16));
+        assert!(!is_within_page(page.wrapping_sub(1), 16));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn system_pages_are_at_least_page_size() {
+        // Page sizes are powers of two, so pages at least as large as the assumed page size are made of whole
+        // assumed pages
+        let system_page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize };
+        assert!(system_page_size >= PAGE_SIZE, "pages are smaller than the assumed page size of {PAGE_SIZE} bytes");
+        assert!(system_page_size.is_power_of_two());
+    }
+
+    // Three pages, of which only the middle one is accessible, so that reading anything before or after it faults
+    #[cfg(unix)]
+    struct GuardedPage {
+        ptr: *mut u8,
+        size: usize,
+    }
+
+    #[cfg(unix)]
+    impl GuardedPage {
+        fn new() -> GuardedPage {
+            unsafe {
+                let size = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+                let ptr = libc::mmap(
+                    std::ptr::null_mut(),
+                    size * 3,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                    -1,
+                    0,
+                );
+                assert_ne!(libc::MAP_FAILED, ptr);
+                let ptr = ptr as *mut u8;
+                assert_eq!(0, libc::mprotect(ptr as *mut libc::c_void, size, libc::PROT_NONE));
+                assert_eq!(0, libc::mprotect(ptr.add(size * 2) as *mut libc::c_void, size, libc::PROT_NONE));
+                GuardedPage { ptr: ptr.add(size), size }
+            }
+        }
+
+        fn page(&mut self) -> &mut [u8] {
+            unsafe { std::slice::from_raw_parts_mut(self.ptr, self.size) }
+        }
+    }
+
+    #[cfg(unix)]
+    impl Drop for GuardedPage {
+        fn drop(&mut self) {
+            unsafe { libc::munmap(self.ptr.sub(self.size) as *mut libc::c_void, self.size * 3) };
+        }
+    }
+
+    #[cfg(unix)]
+    fn assert_hashes_match(input: &[u8]) {
+        use crate::*;
+        use core::hash::Hasher;
+
+        let copy = input.to_vec();
+        assert_eq!(gxhash32(&copy, 42), gxhash32(input, 42));
+        assert_eq!(gxhash64(&copy, 42), gxhash64(input, 42));
+        assert_eq!(gxhash128(&copy, 42), gxhash128(input, 42));
+        assert_eq!(gxhash256(&copy, 42), gxhash256(input, 42));
+
+        let mut hashes = [0u64; 1];
+        gxhash64_batch(&[input], 42, &mut hashes);
+        assert_eq!(gxhash64(&copy, 42), hashes[0]);
+
+        let (mut hasher, mut copy_hasher) = (GxHasher::with_seed(42), GxHasher::with_seed(42));
+        hasher.write(input);
+        copy_hasher.write(&copy);
+        assert_eq!(copy_hasher.finish(), hasher.finish());
+
+        let mut hasher = GxStreamHasher::with_seed_and_len(42, input.len());
+        hasher.write(input);
+        assert_eq!(gxhash128(&copy, 42), hasher.finish_u128());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(miri, ignore)]
+    fn reads_never_cross_into_protected_pages() {
+        let mut guarded = GuardedPage::new();
+        let page = guarded.page();
+        rand::Rng::fill(&mut rand::thread_rng(), &mut page[..]);
+        let size = page.len();
+
+        for len in 0..=300 {
+            // Inputs ending exactly where the protected page after begins
+            assert_hashes_match(&page[size - len..]);
+            // Inputs starting exactly where the protected page before ends
+            assert_hashes_match(&page[..len]);
+        }
+    }
+}
@@ -7,6 +7,9 @@ use super::*;
 
 pub type State = int8x16_t;
 
+// Name of the platform, as returned by gxhash::backend
+pub const BACKEND: &str = "arm-aes";
+
 #[inline(always)]
 pub unsafe fn create_empty() -> State {
     vdupq_n_s8(0)
@@ -27,7 +30,7 @@ pub unsafe fn get_partial_safe(data: *const State, len: usize) -> State {
     // Temporary buffer filled with zeros
     let mut buffer = [0i8; VECTOR_SIZE];
     // Copy data into the buffer
-    std::ptr::copy(data as *const i8, buffer.as_mut_ptr(), len);
+    core::ptr::copy(data as *const i8, buffer.as_mut_ptr(), len);
     // Load the buffer into a __m256i vector
     let partial_vector = vld1q_s8(buffer.as_ptr());
     vaddq_s8(partial_vector, vdupq_n_s8(len as i8))
@@ -66,44 +69,68 @@ pub unsafe fn ld(array: *const u32) -> State {
     vreinterpretq_s8_u32(vld1q_u32(array))
 }
 
-#[inline(always)]
-pub unsafe fn compress_8(mut ptr: *const State, end_address: usize, hash_vector: State, len: usize) -> State {
+/// The two 128-bit lanes [`compress_8`] processes the input in, along with their disambiguation vectors.
+/// Kept in a struct so that the compression can be resumed, when the input comes in several pieces.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Lanes {
+    lane1: State,
+    lane2: State,
+    t1: State,
+    t2: State,
+}
+
+impl Lanes {
+    #[inline(always)]
+    pub unsafe fn new(hash_vector: State) -> Lanes {
+        // Hash is processed in two separate 128-bit parallel lanes
+        // This allows the same processing to be applied using 256-bit V-AES instrinsics
+        // so that hashes are stable in both cases. 
+        Lanes {
+            lane1: hash_vector,
+            lane2: hash_vector,
+            // Disambiguation vectors
+            t1: create_empty(),
+            t2: create_empty(),
+        }
+    }
 
-    // Disambiguation vectors
-    let mut t1: State = create_empty();
-    let mut t2: State = create_empty();
+    #[inline(always)]
+    pub unsafe fn compress(&mut self, mut ptr: *const State, end_address: usize) {
+        while (ptr as usize) < end_address {
 
-    // Hash is processed in two separate 128-bit parallel lanes
-    // This allows the same processing to be applied using 256-bit V-AES instrinsics
-    // so that hashes are stable in both cases. 
-    let mut lane1 = hash_vector;
-    let mut lane2 = hash_vector;
+            crate::gxhash::load_unaligned!(ptr, v0, v1, v2, v3, v4, v5, v6, v7);
 
-    while (ptr as usize) < end_address {
+            let mut tmp1 = aes_encrypt(v0, v2);
+            let mut tmp2 = aes_encrypt(v1, v3);
 
-        crate::gxhash::load_unaligned!(ptr, v0, v1, v2, v3, v4, v5, v6, v7);
+            tmp1 = aes_encrypt(tmp1, v4);
+            tmp2 = aes_encrypt(tmp2, v5);
 
-        let mut tmp1 = aes_encrypt(v0, v2);
-        let mut tmp2 = aes_encrypt(v1, v3);
+            tmp1 = aes_encrypt(tmp1, v6);
+            tmp2 = aes_encrypt(tmp2, v7);
 
-        tmp1 = aes_encrypt(tmp1, v4);
-        tmp2 = aes_encrypt(tmp2, v5);
+            self.t1 = vaddq_s8(self.t1, ld(KEYS.as_ptr()));
+            self.t2 = vaddq_s8(self.t2, ld(KEYS.as_ptr().offset(4)));
 
-        tmp1 = aes_encrypt(tmp1, v6);
-        tmp2 = aes_encrypt(tmp2, v7);
+            self.lane1 = aes_encrypt_last(aes_encrypt(tmp1, self.t1), self.lane1);
+            self.lane2 = aes_encrypt_last(aes_encrypt(tmp2, self.t2), self.lane2);
+        }
+    }
 
-        t1 = vaddq_s8(t1, ld(KEYS.as_ptr()));
-        t2 = vaddq_s8(t2, ld(KEYS.as_ptr().offset(4)));
+    #[inline(always)]
+    pub unsafe fn split(self, len: usize) -> (State, State) {
+        // For 'Zeroes' test
+        let len_vec =  vreinterpretq_s8_u32(vdupq_n_u32(len as u32));
+        (vaddq_s8(self.lane1, len_vec), vaddq_s8(self.lane2, len_vec))
+    }
 
-        lane1 = aes_encrypt_last(aes_encrypt(tmp1, t1), lane1);
-        lane2 = aes_encrypt_last(aes_encrypt(tmp2, t2), lane2);
+    #[inline(always)]
+    pub unsafe fn merge(self, len: usize) -> State {
+        let (lane1, lane2) = self.split(len);
+        // Merge lanes
+        aes_encrypt(lane1, lane2)
     }
-    // For 'Zeroes' test
-    let len_vec =  vreinterpretq_s8_u32(vdupq_n_u32(len as u32));
-    lane1 = vaddq_s8(lane1, len_vec);
-    lane2 = vaddq_s8(lane2, len_vec);
-    // Merge lanes
-    aes_encrypt(lane1, lane2)
 }
 
 #[inline(always)]
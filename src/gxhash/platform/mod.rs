@@ -1,48 +1,136 @@
+#[allow(clippy::module_inception)]
 #[cfg(all(any(target_arch = "arm", target_arch = "aarch64"), target_feature = "aes", target_feature = "neon"))]
 #[path = "arm.rs"]
 mod platform;
 
+#[allow(clippy::module_inception)]
 #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", target_feature = "sse2"))]
 #[path = "x86.rs"]
 mod platform;
 
-pub use platform::*;
+// The portable platform, for targets without AES intrinsics. It is also compiled in tests, to be checked against
+// whatever platform is used on the current target.
+#[cfg(any(test, not(any(
+    all(any(target_arch = "arm", target_arch = "aarch64"), target_feature = "aes", target_feature = "neon"),
+    all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", target_feature = "sse2")))))]
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) mod portable;
 
-use std::mem::size_of;
+#[cfg(not(any(
+    all(any(target_arch = "arm", target_arch = "aarch64"), target_feature = "aes", target_feature = "neon"),
+    all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", target_feature = "sse2"))))]
+use portable as platform;
 
-pub(crate) const VECTOR_SIZE: usize = size_of::<State>();
-// 4KiB is the default page size for most systems, and conservative for other systems such as MacOS ARM (16KiB)
-const PAGE_SIZE: usize = 0x1000;
+pub use platform::*;
 
-#[inline(always)]
-pub unsafe fn get_partial(p: *const State, len: usize) -> State {
-    // Safety check
-    if check_same_page(p) {
-        get_partial_unsafe(p, len)
-    } else {
-        get_partial_safe(p, len)
-    }
-}
+use core::mem::size_of;
 
-#[inline(always)]
-unsafe fn check_same_page(ptr: *const State) -> bool {
-    let address = ptr as usize;
-    // Mask to keep only the last 12 bits
-    let offset_within_page = address & (PAGE_SIZE - 1);
-    // Check if the 16nd byte from the current offset exceeds the page boundary
-    offset_within_page < PAGE_SIZE - VECTOR_SIZE
-}
+pub(crate) const VECTOR_SIZE: usize = size_of::<State>();
 
+// Unlike seeds, which are splatted over the state, keys are the state itself, byte for byte
 #[inline(always)]
-pub unsafe fn finalize(hash: State) -> State {
-    let mut hash = aes_encrypt(hash, ld(KEYS.as_ptr()));
-    hash = aes_encrypt(hash, ld(KEYS.as_ptr().offset(4)));
-    hash = aes_encrypt_last(hash, ld(KEYS.as_ptr().offset(8)));
-
-    hash
+pub(crate) unsafe fn create_key(key: &[u8; 16]) -> State {
+    load_unaligned(key.as_ptr() as *const State)
 }
 
 pub const KEYS: [u32; 12] = 
    [0xF2784542, 0xB09D3E21, 0x89C222E5, 0xFC3BC28E,
     0x03FCE279, 0xCB6B2E9B, 0xB361DC58, 0x39132BD9,
-    0xD0012E32, 0x689D2B7D, 0x5544B1B7, 0xC78B122B];
\ No newline at end of file
+    0xD0012E32, 0x689D2B7D, 0x5544B1B7, 0xC78B122B];
+
+#[cfg(test)]
+mod tests {
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::gxhash::portable;
+
+    fn bytes<T>(state: T) -> [u8; VECTOR_SIZE] {
+        assert_eq!(size_of::<T>(), VECTOR_SIZE);
+        unsafe { std::mem::transmute_copy(&state) }
+    }
+
+    fn random_vectors<const N: usize>() -> [u8; N] {
+        let mut bytes = [0u8; N];
+        rand::thread_rng().fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    #[test]
+    fn portable_aes_matches_platform() {
+        for _ in 0..1000 {
+            let data = random_vectors::<VECTOR_SIZE>();
+            let keys = random_vectors::<VECTOR_SIZE>();
+            unsafe {
+                let (d, k) = (data.as_ptr() as *const State, keys.as_ptr() as *const State);
+                let (pd, pk) = (data.as_ptr() as *const portable::State, keys.as_ptr() as *const portable::State);
+                assert_eq!(bytes(aes_encrypt(load_unaligned(d), load_unaligned(k))),
+                    bytes(portable::aes_encrypt(portable::load_unaligned(pd), portable::load_unaligned(pk))));
+                assert_eq!(bytes(aes_encrypt_last(load_unaligned(d), load_unaligned(k))),
+                    bytes(portable::aes_encrypt_last(portable::load_unaligned(pd), portable::load_unaligned(pk))));
+            }
+        }
+    }
+
+    #[test]
+    fn portable_loads_match_platform() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            unsafe {
+                let x = rng.gen::<u128>();
+                assert_eq!(bytes(create_seed(x as i64)), bytes(portable::create_seed(x as i64)));
+                assert_eq!(bytes(load_u8(x as u8)), bytes(portable::load_u8(x as u8)));
+                assert_eq!(bytes(load_u16(x as u16)), bytes(portable::load_u16(x as u16)));
+                assert_eq!(bytes(load_u32(x as u32)), bytes(portable::load_u32(x as u32)));
+                assert_eq!(bytes(load_u64(x as u64)), bytes(portable::load_u64(x as u64)));
+                assert_eq!(bytes(load_u128(x)), bytes(portable::load_u128(x)));
+                assert_eq!(bytes(load_i8(x as i8)), bytes(portable::load_i8(x as i8)));
+                assert_eq!(bytes(load_i16(x as i16)), bytes(portable::load_i16(x as i16)));
+                assert_eq!(bytes(load_i32(x as i32)), bytes(portable::load_i32(x as i32)));
+                assert_eq!(bytes(load_i64(x as i64)), bytes(portable::load_i64(x as i64)));
+                assert_eq!(bytes(load_i128(x as i128)), bytes(portable::load_i128(x as i128)));
+            }
+        }
+        for i in 0..3 {
+            unsafe {
+                assert_eq!(bytes(ld(KEYS.as_ptr().offset(4 * i))), bytes(portable::ld(KEYS.as_ptr().offset(4 * i))));
+            }
+        }
+    }
+
+    #[test]
+    fn portable_partial_loads_match_platform() {
+        let data = random_vectors::<{ VECTOR_SIZE * 2 }>();
+        for len in 0..=VECTOR_SIZE {
+            unsafe {
+                let (d, pd) = (data.as_ptr() as *const State, data.as_ptr() as *const portable::State);
+                assert_eq!(bytes(get_partial_safe(d, len)), bytes(portable::get_partial_safe(pd, len)));
+                assert_eq!(bytes(get_partial_unsafe(d, len)), bytes(portable::get_partial_unsafe(pd, len)));
+            }
+        }
+    }
+
+    #[test]
+    fn portable_lanes_match_platform() {
+        let data = random_vectors::<{ VECTOR_SIZE * 8 * 5 }>();
+        let hash_vector = random_vectors::<VECTOR_SIZE>();
+        for blocks in 0..=5 {
+            unsafe {
+                let (d, pd) = (data.as_ptr() as *const State, data.as_ptr() as *const portable::State);
+                let end = data.as_ptr() as usize + blocks * VECTOR_SIZE * 8;
+
+                let mut lanes = Lanes::new(load_unaligned(hash_vector.as_ptr() as *const State));
+                lanes.compress(d, end);
+                let mut portable_lanes = portable::Lanes::new(portable::load_unaligned(hash_vector.as_ptr() as *const portable::State));
+                portable_lanes.compress(pd, end);
+
+                assert_eq!(bytes(lanes.merge(blocks * 1000 + 7)), bytes(portable_lanes.merge(blocks * 1000 + 7)));
+                let (lane1, lane2) = lanes.split(blocks * 1000 + 7);
+                let (portable_lane1, portable_lane2) = portable_lanes.split(blocks * 1000 + 7);
+                assert_eq!(bytes(lane1), bytes(portable_lane1));
+                assert_eq!(bytes(lane2), bytes(portable_lane2));
+            }
+        }
+    }
+}
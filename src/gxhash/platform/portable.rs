@@ -0,0 +1,234 @@
+// Portable implementation, for targets without AES intrinsics.
+// The state is a plain array of 16 bytes, laid out as a 128-bit vector would be in memory on little endian
+// targets, and AES rounds are computed in software. This makes hashes identical to the SIMD implementations,
+// at the cost of a much lower throughput.
+
+use super::*;
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C, align(16))]
+pub struct State([u8; 16]);
+
+// Name of the platform, as returned by gxhash::backend
+pub const BACKEND: &str = "portable";
+
+#[inline(always)]
+pub unsafe fn create_empty() -> State {
+    State([0; VECTOR_SIZE])
+}
+
+#[inline(always)]
+pub unsafe fn create_seed(seed: i64) -> State {
+    load_i64(seed)
+}
+
+#[inline(always)]
+pub unsafe fn load_unaligned(p: *const State) -> State {
+    core::ptr::read_unaligned(p)
+}
+
+#[inline(always)]
+pub unsafe fn get_partial_safe(data: *const State, len: usize) -> State {
+    // Temporary buffer filled with zeros
+    let mut buffer = [0u8; VECTOR_SIZE];
+    // Copy data into the buffer
+    core::ptr::copy(data as *const u8, buffer.as_mut_ptr(), len);
+    add_u8(State(buffer), len as u8)
+}
+
+#[inline(always)]
+pub unsafe fn get_partial_unsafe(data: *const State, len: usize) -> State {
+    let mut partial_vector = load_unaligned(data);
+    for byte in partial_vector.0.iter_mut().skip(len) {
+        *byte = 0;
+    }
+    add_u8(partial_vector, len as u8)
+}
+
+#[inline(always)]
+fn add_u8(mut state: State, x: u8) -> State {
+    for byte in state.0.iter_mut() {
+        *byte = byte.wrapping_add(x);
+    }
+    state
+}
+
+#[inline(always)]
+fn add_epi8(mut a: State, b: State) -> State {
+    for (x, y) in a.0.iter_mut().zip(b.0) {
+        *x = x.wrapping_add(y);
+    }
+    a
+}
+
+#[inline(always)]
+fn xor(mut a: State, b: State) -> State {
+    for (x, y) in a.0.iter_mut().zip(b.0) {
+        *x ^= y;
+    }
+    a
+}
+
+/// Forward S-box of AES
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// SubBytes and MixColumns of a single byte, as the column (little endian) it contributes to when on the
+/// first row. Contributions of the other rows are obtained by rotating it.
+const TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let s = SBOX[i];
+        let s2 = (s << 1) ^ (((s >> 7) & 1) * 0x1b);
+        let s3 = s2 ^ s;
+        table[i] = u32::from_le_bytes([s2, s, s, s3]);
+        i += 1;
+    }
+    table
+};
+
+// Bytes of the state are stored column by column, so that the byte at row r of column c is at 4 * c + r.
+// ShiftRows moves the byte at row r of column c + r to column c.
+#[inline(always)]
+fn shifted(state: &State, c: usize, r: usize) -> u8 {
+    state.0[4 * ((c + r) % 4) + r]
+}
+
+#[inline(always)]
+pub unsafe fn aes_encrypt(data: State, keys: State) -> State {
+    let mut encrypted = [0u8; VECTOR_SIZE];
+    for c in 0..4 {
+        let column = TABLE[shifted(&data, c, 0) as usize]
+            ^ TABLE[shifted(&data, c, 1) as usize].rotate_left(8)
+            ^ TABLE[shifted(&data, c, 2) as usize].rotate_left(16)
+            ^ TABLE[shifted(&data, c, 3) as usize].rotate_left(24);
+        encrypted[4 * c..4 * c + 4].copy_from_slice(&column.to_le_bytes());
+    }
+    xor(State(encrypted), keys)
+}
+
+#[inline(always)]
+pub unsafe fn aes_encrypt_last(data: State, keys: State) -> State {
+    let mut encrypted = [0u8; VECTOR_SIZE];
+    for c in 0..4 {
+        for r in 0..4 {
+            encrypted[4 * c + r] = SBOX[shifted(&data, c, r) as usize];
+        }
+    }
+    xor(State(encrypted), keys)
+}
+
+#[inline(always)]
+pub unsafe fn ld(array: *const u32) -> State {
+    let mut bytes = [0u8; VECTOR_SIZE];
+    for (i, chunk) in bytes.chunks_exact_mut(4).enumerate() {
+        chunk.copy_from_slice(&(*array.add(i)).to_le_bytes());
+    }
+    State(bytes)
+}
+
+/// The two 128-bit lanes [`compress_8`] processes the input in, along with their disambiguation vectors.
+/// Kept in a struct so that the compression can be resumed, when the input comes in several pieces.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Lanes {
+    lane1: State,
+    lane2: State,
+    t1: State,
+    t2: State,
+}
+
+impl Lanes {
+    #[inline(always)]
+    pub unsafe fn new(hash_vector: State) -> Lanes {
+        // Hash is processed in two separate 128-bit parallel lanes
+        // This allows the same processing to be applied using 256-bit V-AES instrinsics
+        // so that hashes are stable in both cases.
+        Lanes {
+            lane1: hash_vector,
+            lane2: hash_vector,
+            // Disambiguation vectors
+            t1: create_empty(),
+            t2: create_empty(),
+        }
+    }
+
+    #[inline(always)]
+    pub unsafe fn compress(&mut self, mut ptr: *const State, end_address: usize) {
+        while (ptr as usize) < end_address {
+
+            crate::gxhash::load_unaligned!(ptr, v0, v1, v2, v3, v4, v5, v6, v7);
+
+            let mut tmp1 = aes_encrypt(v0, v2);
+            let mut tmp2 = aes_encrypt(v1, v3);
+
+            tmp1 = aes_encrypt(tmp1, v4);
+            tmp2 = aes_encrypt(tmp2, v5);
+
+            tmp1 = aes_encrypt(tmp1, v6);
+            tmp2 = aes_encrypt(tmp2, v7);
+
+            self.t1 = add_epi8(self.t1, ld(KEYS.as_ptr()));
+            self.t2 = add_epi8(self.t2, ld(KEYS.as_ptr().offset(4)));
+
+            self.lane1 = aes_encrypt_last(aes_encrypt(tmp1, self.t1), self.lane1);
+            self.lane2 = aes_encrypt_last(aes_encrypt(tmp2, self.t2), self.lane2);
+        }
+    }
+
+    #[inline(always)]
+    pub unsafe fn split(self, len: usize) -> (State, State) {
+        // For 'Zeroes' test
+        let len_vec = load_u32(len as u32);
+        (add_epi8(self.lane1, len_vec), add_epi8(self.lane2, len_vec))
+    }
+
+    #[inline(always)]
+    pub unsafe fn merge(self, len: usize) -> State {
+        let (lane1, lane2) = self.split(len);
+        // Merge lanes
+        aes_encrypt(lane1, lane2)
+    }
+}
+
+// Scalars are broadcasted to every lane of their size, in little endian order
+macro_rules! load_splat {
+    ($name:ident, $type:ty) => {
+        #[inline(always)]
+        pub unsafe fn $name(x: $type) -> State {
+            let mut bytes = [0u8; VECTOR_SIZE];
+            for chunk in bytes.chunks_exact_mut(core::mem::size_of::<$type>()) {
+                chunk.copy_from_slice(&x.to_le_bytes());
+            }
+            State(bytes)
+        }
+    };
+}
+
+load_splat!(load_u8, u8);
+load_splat!(load_u16, u16);
+load_splat!(load_u32, u32);
+load_splat!(load_u64, u64);
+load_splat!(load_u128, u128);
+load_splat!(load_i8, i8);
+load_splat!(load_i16, i16);
+load_splat!(load_i32, i32);
+load_splat!(load_i64, i64);
+load_splat!(load_i128, i128);
@@ -7,6 +7,12 @@ use super::*;
 
 pub type State = __m128i;
 
+// Name of the platform, as returned by gxhash::backend
+#[cfg(not(hybrid))]
+pub const BACKEND: &str = "x86-aes";
+#[cfg(hybrid)]
+pub const BACKEND: &str = "x86-vaes";
+
 #[inline(always)]
 pub unsafe fn create_empty() -> State {
     _mm_setzero_si128()
@@ -27,7 +33,7 @@ pub unsafe fn get_partial_safe(data: *const State, len: usize) -> State {
     // Temporary buffer filled with zeros
     let mut buffer = [0i8; VECTOR_SIZE];
     // Copy data into the buffer
-    std::ptr::copy(data as *const i8, buffer.as_mut_ptr(), len);
+    core::ptr::copy(data as *const i8, buffer.as_mut_ptr(), len);
     // Load the buffer into a __m256i vector
     let partial_vector = _mm_loadu_si128(buffer.as_ptr() as *const State);
     _mm_add_epi8(partial_vector, _mm_set1_epi8(len as i8))
@@ -59,84 +65,134 @@ pub unsafe fn ld(array: *const u32) -> State {
     _mm_loadu_si128(array as *const State)
 }
 
+/// The two 128-bit lanes [`compress_8`] processes the input in, along with their disambiguation vectors.
+/// Kept in a struct so that the compression can be resumed, when the input comes in several pieces.
 #[cfg(not(hybrid))]
-#[inline(always)]
-pub unsafe fn compress_8(mut ptr: *const State, end_address: usize, hash_vector: State, len: usize) -> State {
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Lanes {
+    lane1: State,
+    lane2: State,
+    t1: State,
+    t2: State,
+}
 
-    // Disambiguation vectors
-    let mut t1: State = create_empty();
-    let mut t2: State = create_empty();
+#[cfg(not(hybrid))]
+impl Lanes {
+    #[inline(always)]
+    pub unsafe fn new(hash_vector: State) -> Lanes {
+        // Hash is processed in two separate 128-bit parallel lanes
+        // This allows the same processing to be applied using 256-bit V-AES instrinsics
+        // so that hashes are stable in both cases. 
+        Lanes {
+            lane1: hash_vector,
+            lane2: hash_vector,
+            // Disambiguation vectors
+            t1: create_empty(),
+            t2: create_empty(),
+        }
+    }
 
-    // Hash is processed in two separate 128-bit parallel lanes
-    // This allows the same processing to be applied using 256-bit V-AES instrinsics
-    // so that hashes are stable in both cases. 
-    let mut lane1 = hash_vector;
-    let mut lane2 = hash_vector;
+    #[inline(always)]
+    pub unsafe fn compress(&mut self, mut ptr: *const State, end_address: usize) {
+        while (ptr as usize) < end_address {
 
-    while (ptr as usize) < end_address {
+            crate::gxhash::load_unaligned!(ptr, v0, v1, v2, v3, v4, v5, v6, v7);
 
-        crate::gxhash::load_unaligned!(ptr, v0, v1, v2, v3, v4, v5, v6, v7);
+            let mut tmp1 = aes_encrypt(v0, v2);
+            let mut tmp2 = aes_encrypt(v1, v3);
 
-        let mut tmp1 = aes_encrypt(v0, v2);
-        let mut tmp2 = aes_encrypt(v1, v3);
+            tmp1 = aes_encrypt(tmp1, v4);
+            tmp2 = aes_encrypt(tmp2, v5);
 
-        tmp1 = aes_encrypt(tmp1, v4);
-        tmp2 = aes_encrypt(tmp2, v5);
+            tmp1 = aes_encrypt(tmp1, v6);
+            tmp2 = aes_encrypt(tmp2, v7);
 
-        tmp1 = aes_encrypt(tmp1, v6);
-        tmp2 = aes_encrypt(tmp2, v7);
+            self.t1 = _mm_add_epi8(self.t1, ld(KEYS.as_ptr()));
+            self.t2 = _mm_add_epi8(self.t2, ld(KEYS.as_ptr().offset(4)));
 
-        t1 = _mm_add_epi8(t1, ld(KEYS.as_ptr()));
-        t2 = _mm_add_epi8(t2, ld(KEYS.as_ptr().offset(4)));
+            self.lane1 = aes_encrypt_last(aes_encrypt(tmp1, self.t1), self.lane1);
+            self.lane2 = aes_encrypt_last(aes_encrypt(tmp2, self.t2), self.lane2);
+        }
+    }
+
+    #[inline(always)]
+    pub unsafe fn split(self, len: usize) -> (State, State) {
+        // For 'Zeroes' test
+        let len_vec =  _mm_set1_epi32(len as i32);
+        (_mm_add_epi8(self.lane1, len_vec), _mm_add_epi8(self.lane2, len_vec))
+    }
 
-        lane1 = aes_encrypt_last(aes_encrypt(tmp1, t1), lane1);
-        lane2 = aes_encrypt_last(aes_encrypt(tmp2, t2), lane2);
+    #[inline(always)]
+    pub unsafe fn merge(self, len: usize) -> State {
+        let (lane1, lane2) = self.split(len);
+        // Merge lanes
+        aes_encrypt(lane1, lane2)
     }
-    // For 'Zeroes' test
-    let len_vec =  _mm_set1_epi32(len as i32);
-    lane1 = _mm_add_epi8(lane1, len_vec);
-    lane2 = _mm_add_epi8(lane2, len_vec);
-    // Merge lanes
-    aes_encrypt(lane1, lane2)
 }
 
+/// The two 128-bit lanes [`compress_8`] processes the input in, both held in a single 256-bit vector.
+/// Kept in a struct so that the compression can be resumed, when the input comes in several pieces.
 #[cfg(hybrid)]
-#[inline(always)]
-pub unsafe fn compress_8(ptr: *const State, end_address: usize, hash_vector: State, len: usize) -> State {
-    macro_rules! load_unaligned_x2 {
-        ($ptr:ident, $($var:ident),+) => {
-            $(
-                #[allow(unused_mut)]
-                let mut $var = _mm256_loadu_si256($ptr);
-                $ptr = ($ptr).offset(1);
-            )+
-        };
+#[derive(Clone, Copy, Debug)]
+pub struct Lanes {
+    lane: __m256i,
+    t: __m256i,
+}
+
+#[cfg(hybrid)]
+impl Lanes {
+    #[inline(always)]
+    pub unsafe fn new(hash_vector: State) -> Lanes {
+        Lanes {
+            lane: _mm256_set_m128i(hash_vector, hash_vector),
+            t: _mm256_setzero_si256(),
+        }
     }
-    
-    let mut ptr = ptr as *const __m256i;
-    let mut t = _mm256_setzero_si256();
-    let mut lane = _mm256_set_m128i(hash_vector, hash_vector);
-    while (ptr as usize) < end_address {
 
-        load_unaligned_x2!(ptr, v0, v1, v2, v3);
+    #[inline(always)]
+    pub unsafe fn compress(&mut self, ptr: *const State, end_address: usize) {
+        macro_rules! load_unaligned_x2 {
+            ($ptr:ident, $($var:ident),+) => {
+                $(
+                    #[allow(unused_mut)]
+                    let mut $var = _mm256_loadu_si256($ptr);
+                    $ptr = ($ptr).offset(1);
+                )+
+            };
+        }
+
+        let mut ptr = ptr as *const __m256i;
+        while (ptr as usize) < end_address {
 
-        let mut tmp = _mm256_aesenc_epi128(v0, v1);
-        tmp = _mm256_aesenc_epi128(tmp, v2);
-        tmp = _mm256_aesenc_epi128(tmp, v3);
+            load_unaligned_x2!(ptr, v0, v1, v2, v3);
 
-        t = _mm256_add_epi8(t, _mm256_loadu_si256(KEYS.as_ptr() as *const __m256i));
+            let mut tmp = _mm256_aesenc_epi128(v0, v1);
+            tmp = _mm256_aesenc_epi128(tmp, v2);
+            tmp = _mm256_aesenc_epi128(tmp, v3);
+
+            self.t = _mm256_add_epi8(self.t, _mm256_loadu_si256(KEYS.as_ptr() as *const __m256i));
+
+            self.lane = _mm256_aesenclast_epi128(_mm256_aesenc_epi128(tmp, self.t), self.lane);
+        }
+    }
+
+    #[inline(always)]
+    pub unsafe fn split(self, len: usize) -> (State, State) {
+        // Extract the two 128-bit lanes
+        let lane1 = _mm256_castsi256_si128(self.lane);
+        let lane2 = _mm256_extracti128_si256(self.lane, 1);
+        // For 'Zeroes' test
+        let len_vec =  _mm_set1_epi32(len as i32);
+        (_mm_add_epi8(lane1, len_vec), _mm_add_epi8(lane2, len_vec))
+    }
 
-        lane = _mm256_aesenclast_epi128(_mm256_aesenc_epi128(tmp, t), lane);
+    #[inline(always)]
+    pub unsafe fn merge(self, len: usize) -> State {
+        let (lane1, lane2) = self.split(len);
+        // Merge lanes
+        aes_encrypt(lane1, lane2)
     }
-    // Extract the two 128-bit lanes
-    let mut lane1 = _mm256_castsi256_si128(lane);
-    let mut lane2 = _mm256_extracti128_si256(lane, 1);
-    // For 'Zeroes' test
-    let len_vec =  _mm_set1_epi32(len as i32);
-    lane1 = _mm_add_epi8(lane1, len_vec);
-    lane2 = _mm_add_epi8(lane2, len_vec);
-    // Merge lanes
-    aes_encrypt(lane1, lane2)
 }
 
 #[inline(always)]
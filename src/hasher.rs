@@ -1,12 +1,16 @@
+use core::hash::{BuildHasher, Hasher};
+#[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
-use std::hash::{BuildHasher, Hasher};
-use std::mem::MaybeUninit;
 
+#[cfg(feature = "std")]
 use rand::RngCore;
 
 use crate::gxhash::platform::*;
 use crate::gxhash::*;
 
+// `GxHasher` is re-exported as `crate::v3::GxHasher`: changing its hashes requires copying it into that module
+// first (see src/v3.rs).
+
 /// A `Hasher` for hashing an arbitrary stream of bytes.
 /// # Features
 /// - The fastest [`Hasher`] of its class<sup>1</sup>, for all input sizes
@@ -80,15 +84,35 @@ impl GxHasher {
         GxHasher::with_state(unsafe { create_seed(seed) })
     }
 
+    /// Creates a new hasher using the provided 128-bit key, as with [`gxhash128_with_key`](crate::gxhash128_with_key).
+    /// The key space is twice as large as the one of [`GxHasher::with_seed`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::Hasher;
+    /// use gxhash::GxHasher;
+    ///
+    /// let mut hasher = GxHasher::with_key(&[7u8; 16]);
+    ///
+    /// hasher.write(b"Hello");
+    ///
+    /// println!("Hash is {:x}!", hasher.finish());
+    /// ```
+    #[inline]
+    pub fn with_key(key: &[u8; 16]) -> GxHasher {
+        GxHasher::with_state(unsafe { create_key(key) })
+    }
+
     /// Finish this hasher and return the hashed value as a 128 bit
     /// unsigned integer.
     #[inline]
     pub fn finish_u128(&self) -> u128 {
-        debug_assert!(std::mem::size_of::<State>() >= std::mem::size_of::<u128>());
+        debug_assert!(core::mem::size_of::<State>() >= core::mem::size_of::<u128>());
 
         unsafe {
             let p = &finalize(self.state) as *const State as *const u128;
-            *p
+            u128::from_le(*p)
         }
     }
 }
@@ -97,9 +121,7 @@ macro_rules! write {
     ($name:ident, $type:ty, $load:expr) => {
         #[inline]
         fn $name(&mut self, value: $type) {
-            self.state = unsafe {
-                aes_encrypt_last($load(value), aes_encrypt(self.state, ld(KEYS.as_ptr())))
-            };
+            self.state = unsafe { mix(self.state, $load(value)) };
         }
     }
 }
@@ -109,14 +131,14 @@ impl Hasher for GxHasher {
     fn finish(&self) -> u64 {
         unsafe {
             let p = &finalize(self.state) as *const State as *const u64;
-            *p
+            u64::from_le(*p)
         }
     }
 
     #[inline]
     fn write(&mut self, bytes: &[u8]) {
         // Improvement: only compress at this stage and finalize in finish
-        self.state = unsafe { aes_encrypt_last(compress_all(bytes), aes_encrypt(self.state, ld(KEYS.as_ptr()))) };
+        self.state = unsafe { mix(self.state, compress_all(bytes)) };
     }
 
     write!(write_u8, u8, load_u8);
@@ -135,17 +157,76 @@ impl Hasher for GxHasher {
 #[derive(Clone, Debug)]
 pub struct GxBuildHasher(State);
 
+impl GxBuildHasher {
+    /// Creates a new builder of hashers using the provided seed, as with [`GxHasher::with_seed`].
+    /// Unlike [`GxBuildHasher::default()`], this is available without the 'std' feature.
+    ///
+    /// # Warning ⚠️
+    /// Hardcoding a seed may make your [`Hasher`] vulnerable to DOS attacks.
+    /// It is recommended to use [`GxBuildHasher::default()`] for improved DOS resistance.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::{BuildHasher, Hasher};
+    /// use gxhash::{GxBuildHasher, GxHasher};
+    ///
+    /// let mut hasher = GxBuildHasher::with_seed(1234).build_hasher();
+    /// hasher.write_u32(42);
+    ///
+    /// let mut expected = GxHasher::with_seed(1234);
+    /// expected.write_u32(42);
+    ///
+    /// assert_eq!(hasher.finish(), expected.finish());
+    /// ```
+    #[inline]
+    pub fn with_seed(seed: i64) -> GxBuildHasher {
+        GxBuildHasher(unsafe { create_seed(seed) })
+    }
+
+    /// Creates a new builder of hashers using the provided 128-bit state, as exported by
+    /// [`GxBuildHasher::state_bytes`]. Hashers built from the same state produce the same hashes,
+    /// which allows persisting a randomly seeded builder, or sharing it between processes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::BuildHasher;
+    /// use gxhash::GxBuildHasher;
+    ///
+    /// let build_hasher = GxBuildHasher::default();
+    /// let restored = GxBuildHasher::with_state_bytes(build_hasher.state_bytes());
+    ///
+    /// assert_eq!(build_hasher.hash_one("hello"), restored.hash_one("hello"));
+    /// ```
+    #[inline]
+    pub fn with_state_bytes(bytes: [u8; 16]) -> GxBuildHasher {
+        GxBuildHasher(unsafe { create_key(&bytes) })
+    }
+
+    /// Creates a new builder of hashers with a state drawn from the provided random number generator.
+    /// Using a seeded generator makes the builder reproducible.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn from_rng(rng: &mut impl RngCore) -> GxBuildHasher {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        GxBuildHasher::with_state_bytes(bytes)
+    }
+
+    /// Returns the 128-bit state hashers are built from, so that it can be restored with
+    /// [`GxBuildHasher::with_state_bytes`].
+    #[inline]
+    pub fn state_bytes(&self) -> [u8; 16] {
+        unsafe { *(&self.0 as *const State as *const [u8; 16]) }
+    }
+}
+
+#[cfg(feature = "std")]
 impl Default for GxBuildHasher {
     #[inline]
     fn default() -> GxBuildHasher {
-        let mut uninit: MaybeUninit<State> = MaybeUninit::uninit();
-        let mut rng = rand::thread_rng();
-        unsafe {
-            let ptr = uninit.as_mut_ptr() as *mut u8;
-            let slice = std::slice::from_raw_parts_mut(ptr, VECTOR_SIZE);
-            rng.fill_bytes(slice);
-            GxBuildHasher(uninit.assume_init())
-        }
+        GxBuildHasher::from_rng(&mut rand::thread_rng())
     }
 }
 
@@ -158,19 +239,23 @@ impl BuildHasher for GxBuildHasher {
 }
 
 /// A `HashMap` using a (DOS-resistant) [`GxBuildHasher`].
+#[cfg(feature = "std")]
 pub type GxHashMap<K, V> = HashMap<K, V, GxBuildHasher>;
 
 /// A `HashSet` using a (DOS-resistant) [`GxBuildHasher`].
+#[cfg(feature = "std")]
 pub type GxHashSet<T> = HashSet<T, GxBuildHasher>;
 
 #[cfg(test)]
 mod tests {
 
+    #[cfg(feature = "std")]
     use std::hash::Hash;
 
     use super::*;
 
     #[test]
+    #[cfg(feature = "std")]
     fn hasher_produces_stable_hashes() {
         let mut hashset = GxHashSet::default();
         assert!(hashset.insert(1234));
@@ -186,6 +271,8 @@ mod tests {
 
     // By no mean a quality test, but rather a sanity check
     #[test]
+    #[cfg(feature = "std")]
+    #[allow(clippy::manual_hash_one)]
     fn hasher_resists_permutations() {
         let build_hasher = GxBuildHasher::default();
         let mut hasher1 = build_hasher.build_hasher();
@@ -197,6 +284,7 @@ mod tests {
 
     // This is important for DOS resistance
     #[test]
+    #[cfg(feature = "std")]
     fn gxhashset_uses_default_gxhasherbuilder() {
         let hashset_1 = GxHashSet::<u32>::default();
         let hashset_2 = GxHashSet::<u32>::default();
@@ -215,6 +303,7 @@ mod tests {
 
     // This is important for DOS resistance
     #[test]
+    #[cfg(feature = "std")]
     fn default_gxhasherbuilder_is_randomly_seeded() {
         let buildhasher_1 = GxBuildHasher::default();
         let buildhasher_2 = GxBuildHasher::default();
@@ -232,6 +321,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn gxhasherbuilder_builds_same_hashers() {
         let buildhasher = GxBuildHasher::default();
 
@@ -245,4 +335,62 @@ mod tests {
         hasher.write_i32(42);
         assert_eq!(hash, hasher.finish());
     }
+
+    #[test]
+    fn seeded_gxhasherbuilder_builds_seeded_hashers() {
+        let buildhasher = GxBuildHasher::with_seed(1234);
+
+        let mut hasher = buildhasher.build_hasher();
+        hasher.write_i32(42);
+
+        let mut expected = GxHasher::with_seed(1234);
+        expected.write_i32(42);
+
+        assert_eq!(expected.finish(), hasher.finish());
+        assert_ne!(GxBuildHasher::with_seed(4321).hash_one(42), buildhasher.hash_one(42));
+    }
+
+    #[test]
+    fn gxhasherbuilder_state_bytes_roundtrip() {
+        let buildhasher = GxBuildHasher::with_seed(1234);
+        let restored = GxBuildHasher::with_state_bytes(buildhasher.state_bytes());
+
+        assert_eq!(buildhasher.state_bytes(), restored.state_bytes());
+        assert_eq!(buildhasher.hash_one(42), restored.hash_one(42));
+    }
+
+    #[test]
+    fn gxhasherbuilder_state_bytes_are_seed_bytes() {
+        let seed = 0x0123456789ABCDEFi64;
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&seed.to_le_bytes());
+        bytes[8..].copy_from_slice(&seed.to_le_bytes());
+
+        assert_eq!(bytes, GxBuildHasher::with_seed(seed).state_bytes());
+        assert_eq!(GxBuildHasher::with_seed(seed).hash_one(42), GxBuildHasher::with_state_bytes(bytes).hash_one(42));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn gxhasherbuilder_from_seeded_rng_is_reproducible() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let buildhasher_1 = GxBuildHasher::from_rng(&mut StdRng::seed_from_u64(42));
+        let buildhasher_2 = GxBuildHasher::from_rng(&mut StdRng::seed_from_u64(42));
+        let buildhasher_3 = GxBuildHasher::from_rng(&mut StdRng::seed_from_u64(43));
+
+        assert_eq!(buildhasher_1.hash_one(42), buildhasher_2.hash_one(42));
+        assert_ne!(buildhasher_1.hash_one(42), buildhasher_3.hash_one(42));
+    }
+
+    #[test]
+    fn keyed_hasher_matches_state_bytes() {
+        let key = [7u8; 16];
+
+        let mut hasher = GxHasher::with_key(&key);
+        hasher.write_i32(42);
+
+        assert_eq!(GxBuildHasher::with_state_bytes(key).hash_one(42i32), hasher.finish());
+        assert_ne!(GxHasher::with_key(&[8u8; 16]).finish(), GxHasher::with_key(&key).finish());
+    }
 }
@@ -0,0 +1,406 @@
+use std::hash::Hasher;
+use std::io::{self, Read, Write};
+
+use crate::GxStreamHasher;
+
+/// A [`Read`] adapter hashing the bytes read through it, with the same hashes as [`gxhash64`] and
+/// [`gxhash128`] on the whole of these bytes.
+///
+/// As with [`GxStreamHasher`], the bytes are only compressed as they come when their length is known upfront
+/// (see [`GxHashReader::with_len`]). Otherwise, they are buffered until the hash is requested.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Read;
+/// use gxhash::GxHashReader;
+///
+/// let mut reader = GxHashReader::with_len(&b"hello world"[..], 1234, 11);
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents).unwrap();
+///
+/// assert_eq!(reader.finish_u128(), gxhash::gxhash128(contents.as_bytes(), 1234));
+/// ```
+///
+/// [`gxhash64`]: crate::gxhash64
+/// [`gxhash128`]: crate::gxhash128
+#[derive(Debug)]
+pub struct GxHashReader<R> {
+    inner: R,
+    hasher: GxStreamHasher,
+    remaining: Option<usize>,
+}
+
+impl<R: Read> GxHashReader<R> {
+    /// Creates a new reader hashing all bytes read from `inner` using the provided seed.
+    ///
+    /// # Warning ⚠️
+    /// All bytes read are buffered in memory until the hash is requested, as the hash depends on the length of
+    /// the input. Inputs of known length are hashed in constant memory with [`GxHashReader::with_len`].
+    #[inline]
+    pub fn new(inner: R, seed: i64) -> GxHashReader<R> {
+        GxHashReader { inner, hasher: GxStreamHasher::with_seed(seed), remaining: None }
+    }
+
+    /// Creates a new reader hashing the first `len` bytes of `inner` using the provided seed.
+    /// The bytes are compressed as they are read, and reading stops after `len` bytes.
+    #[inline]
+    pub fn with_len(inner: R, seed: i64, len: usize) -> GxHashReader<R> {
+        GxHashReader { inner, hasher: GxStreamHasher::with_seed_and_len(seed, len), remaining: Some(len) }
+    }
+}
+
+impl<R> GxHashReader<R> {
+    /// Returns the hash of the bytes read so far, as with [`Hasher::finish`].
+    ///
+    /// # Panics
+    /// Panics if the reader was created with [`GxHashReader::with_len`] and fewer bytes were read.
+    #[inline]
+    pub fn finish(&self) -> u64 {
+        // Low bits of the 128-bit hash, which unlike Hasher::finish panics on missing bytes
+        self.hasher.finish_u128() as u64
+    }
+
+    /// Returns the hash of the bytes read so far as a 128 bit unsigned integer.
+    ///
+    /// # Panics
+    /// Panics if the reader was created with [`GxHashReader::with_len`] and fewer bytes were read.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        self.hasher.finish_u128()
+    }
+
+    /// Gets a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    /// Reading from it directly skips the hashing of the bytes read.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for GxHashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let buf = match self.remaining {
+            Some(remaining) if remaining < buf.len() => &mut buf[..remaining],
+            _ => buf,
+        };
+        let read = self.inner.read(buf)?;
+        self.hasher.write(&buf[..read]);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= read;
+        }
+        Ok(read)
+    }
+}
+
+/// A [`Write`] adapter hashing the bytes written through it, with the same hashes as [`gxhash64`] and
+/// [`gxhash128`] on the whole of these bytes.
+///
+/// As with [`GxStreamHasher`], the bytes are only compressed as they come when their length is known upfront
+/// (see [`GxHashWriter::with_len`]). Otherwise, they are buffered until the hash is requested.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Write;
+/// use gxhash::GxHashWriter;
+///
+/// let mut writer = GxHashWriter::with_len(Vec::new(), 1234, 11);
+/// writer.write_all(b"hello ").unwrap();
+/// writer.write_all(b"world").unwrap();
+///
+/// assert_eq!(writer.finish_u128(), gxhash::gxhash128(b"hello world", 1234));
+/// assert_eq!(writer.get_ref(), b"hello world");
+/// ```
+///
+/// [`gxhash64`]: crate::gxhash64
+/// [`gxhash128`]: crate::gxhash128
+#[derive(Debug)]
+pub struct GxHashWriter<W> {
+    inner: W,
+    hasher: GxStreamHasher,
+    remaining: Option<usize>,
+}
+
+impl<W: Write> GxHashWriter<W> {
+    /// Creates a new writer hashing all bytes written to `inner` using the provided seed.
+    ///
+    /// # Warning ⚠️
+    /// All bytes written are buffered in memory until the hash is requested, as the hash depends on the length of
+    /// the input. Inputs of known length are hashed in constant memory with [`GxHashWriter::with_len`].
+    #[inline]
+    pub fn new(inner: W, seed: i64) -> GxHashWriter<W> {
+        GxHashWriter { inner, hasher: GxStreamHasher::with_seed(seed), remaining: None }
+    }
+
+    /// Creates a new writer hashing exactly `len` bytes written to `inner` using the provided seed.
+    /// The bytes are compressed as they are written, and writing more than `len` bytes fails.
+    #[inline]
+    pub fn with_len(inner: W, seed: i64, len: usize) -> GxHashWriter<W> {
+        GxHashWriter { inner, hasher: GxStreamHasher::with_seed_and_len(seed, len), remaining: Some(len) }
+    }
+}
+
+impl<W> GxHashWriter<W> {
+    /// Returns the hash of the bytes written so far, as with [`Hasher::finish`].
+    ///
+    /// # Panics
+    /// Panics if the writer was created with [`GxHashWriter::with_len`] and fewer bytes were written.
+    #[inline]
+    pub fn finish(&self) -> u64 {
+        // Low bits of the 128-bit hash, which unlike Hasher::finish panics on missing bytes
+        self.hasher.finish_u128() as u64
+    }
+
+    /// Returns the hash of the bytes written so far as a 128 bit unsigned integer.
+    ///
+    /// # Panics
+    /// Panics if the writer was created with [`GxHashWriter::with_len`] and fewer bytes were written.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        self.hasher.finish_u128()
+    }
+
+    /// Gets a reference to the underlying writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    /// Writing to it directly skips the hashing of the bytes written.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this writer, returning the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for GxHashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let buf = match self.remaining {
+            Some(0) if !buf.is_empty() => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "more bytes written than the announced length"))
+            }
+            Some(remaining) if remaining < buf.len() => &buf[..remaining],
+            _ => buf,
+        };
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= written;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hashes all bytes of a reader to an u128, with the same result as [`gxhash128`] on these bytes.
+///
+/// # Warning ⚠️
+/// The whole input is buffered in memory, as its length isn't known upfront. When it is, such as for files,
+/// [`gxhash128_reader_with_len`] hashes it in constant memory. Inputs of unbounded size, such as network streams,
+/// are hashed with a cap on the memory used by [`gxhash128_reader_with_limit`].
+///
+/// # Example
+///
+/// ```
+/// let hash = gxhash::gxhash128_reader(&b"hello world"[..], 1234).unwrap();
+/// assert_eq!(hash, gxhash::gxhash128(b"hello world", 1234));
+/// ```
+///
+/// [`gxhash128`]: crate::gxhash128
+pub fn gxhash128_reader(reader: impl Read, seed: i64) -> io::Result<u128> {
+    let mut reader = GxHashReader::new(reader, seed);
+    io::copy(&mut reader, &mut io::sink())?;
+    Ok(reader.finish_u128())
+}
+
+/// Hashes all bytes of a reader to an u128, as [`gxhash128_reader`] does, buffering at most `limit` bytes.
+///
+/// # Errors
+/// Fails with [`io::ErrorKind::InvalidData`] if the reader has more than `limit` bytes, once `limit` bytes are
+/// buffered and without reading the rest of the input.
+///
+/// # Example
+///
+/// ```
+/// let hash = gxhash::gxhash128_reader_with_limit(&b"hello world"[..], 1234, 1024).unwrap();
+/// assert_eq!(hash, gxhash::gxhash128(b"hello world", 1234));
+///
+/// assert!(gxhash::gxhash128_reader_with_limit(&b"hello world"[..], 1234, 5).is_err());
+/// ```
+pub fn gxhash128_reader_with_limit(reader: impl Read, seed: i64, limit: usize) -> io::Result<u128> {
+    // One extra byte tells inputs of exactly `limit` bytes apart from longer ones
+    let mut reader = GxHashReader::new(reader.take((limit as u64).saturating_add(1)), seed);
+    if io::copy(&mut reader, &mut io::sink())? > limit as u64 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "input is longer than the limit"));
+    }
+    Ok(reader.finish_u128())
+}
+
+/// Hashes the first `len` bytes of a reader to an u128, with the same result as [`gxhash128`] on these bytes.
+/// The bytes are compressed as they are read, in constant memory.
+///
+/// # Errors
+/// Fails with [`io::ErrorKind::UnexpectedEof`] if the reader has fewer than `len` bytes.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let file = File::open("snapshot.bin")?;
+/// let len = file.metadata()?.len() as usize;
+/// println!("Hash is {:x}!", gxhash::gxhash128_reader_with_len(file, 1234, len)?);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// [`gxhash128`]: crate::gxhash128
+pub fn gxhash128_reader_with_len(reader: impl Read, seed: i64, len: usize) -> io::Result<u128> {
+    let mut reader = GxHashReader::with_len(reader, seed, len);
+    io::copy(&mut reader, &mut io::sink())?;
+    if reader.remaining != Some(0) {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input ended before the announced length"));
+    }
+    Ok(reader.finish_u128())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::{gxhash128, gxhash64};
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        rand::thread_rng().fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    // Large inputs take too long under Miri
+    const LENGTHS: &[usize] = if cfg!(miri) {
+        &[0, 1, 15, 16, 17, 64, 65, 128, 129, 1000]
+    } else {
+        &[0, 1, 15, 16, 17, 64, 65, 128, 129, 1000, 100_000]
+    };
+
+    // A reader returning its bytes in small pieces of random sizes, as sockets may do
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = rand::thread_rng().gen_range(0..=7).min(buf.len()).min(self.0.len());
+            if len == 0 && !self.0.is_empty() && !buf.is_empty() {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn reader_matches_oneshot() {
+        for &len in LENGTHS {
+            let bytes = random_bytes(len);
+            let expected = gxhash128(&bytes, 42);
+
+            assert_eq!(expected, gxhash128_reader(Cursor::new(&bytes), 42).unwrap());
+            assert_eq!(expected, gxhash128_reader(Trickle(&bytes), 42).unwrap());
+            assert_eq!(expected, gxhash128_reader_with_len(Cursor::new(&bytes), 42, len).unwrap());
+            assert_eq!(expected, gxhash128_reader_with_len(Trickle(&bytes), 42, len).unwrap());
+
+            let mut reader = GxHashReader::with_len(Cursor::new(&bytes), 42, len);
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents).unwrap();
+            assert_eq!(bytes, contents);
+            assert_eq!(gxhash64(&bytes, 42), reader.finish());
+        }
+    }
+
+    #[test]
+    fn reader_with_len_stops_at_len() {
+        let bytes = random_bytes(1000);
+        assert_eq!(gxhash128(&bytes[..600], 0), gxhash128_reader_with_len(Cursor::new(&bytes), 0, 600).unwrap());
+
+        let mut reader = GxHashReader::with_len(Cursor::new(&bytes), 0, 600);
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(600, reader.get_ref().position());
+    }
+
+    #[test]
+    fn reader_with_len_rejects_short_input() {
+        let error = gxhash128_reader_with_len(Cursor::new(&[0u8; 10]), 0, 11).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[test]
+    fn reader_with_limit_rejects_long_input() {
+        let bytes = random_bytes(1000);
+        assert_eq!(gxhash128(&bytes, 0), gxhash128_reader_with_limit(Trickle(&bytes), 0, 1000).unwrap());
+        assert_eq!(gxhash128(&bytes, 0), gxhash128_reader_with_limit(Cursor::new(&bytes), 0, usize::MAX).unwrap());
+
+        let error = gxhash128_reader_with_limit(Cursor::new(&bytes), 0, 999).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+
+        // Reading stops right after the limit
+        let mut cursor = Cursor::new(&bytes);
+        let error = gxhash128_reader_with_limit(&mut cursor, 0, 10).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert_eq!(11, cursor.position());
+    }
+
+    #[test]
+    fn writer_matches_oneshot() {
+        for &len in LENGTHS {
+            let bytes = random_bytes(len);
+
+            let mut writer = GxHashWriter::new(Vec::new(), 42);
+            let mut sized_writer = GxHashWriter::with_len(Vec::new(), 42, len);
+            for chunk in bytes.chunks(333) {
+                writer.write_all(chunk).unwrap();
+                sized_writer.write_all(chunk).unwrap();
+            }
+
+            assert_eq!(gxhash128(&bytes, 42), writer.finish_u128());
+            assert_eq!(gxhash128(&bytes, 42), sized_writer.finish_u128());
+            assert_eq!(bytes, writer.into_inner());
+            assert_eq!(bytes, sized_writer.into_inner());
+        }
+    }
+
+    #[test]
+    fn writer_with_len_rejects_extra_bytes() {
+        let mut writer = GxHashWriter::with_len(Vec::new(), 0, 4);
+        let error = writer.write_all(b"hello").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+        // Bytes up to the announced length still went through
+        assert_eq!(b"hell", writer.get_ref().as_slice());
+        assert_eq!(gxhash128(b"hell", 0), writer.finish_u128());
+    }
+}
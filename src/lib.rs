@@ -1,9 +1,32 @@
 // Hybrid SIMD width usage currently requires unstable 'stdsimd'
 #![cfg_attr(hybrid, feature(stdarch_x86_avx512))]
+// The core hash functions only need `core`, features requiring an allocator or an OS are behind 'std'
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+mod bucket;
+pub mod cdc;
+mod combine;
+mod digest;
 #[rustfmt::skip]
 mod gxhash;
 mod hasher;
+#[cfg(feature = "std")]
+mod io;
+pub mod reference;
+#[cfg(feature = "std")]
+pub mod ring;
+mod stream;
+#[cfg(feature = "std")]
+mod tree;
+pub mod v3;
 
+pub use crate::bucket::*;
+pub use crate::combine::*;
+pub use crate::digest::*;
 pub use crate::gxhash::*;
-pub use crate::hasher::*;
\ No newline at end of file
+pub use crate::hasher::*;
+#[cfg(feature = "std")]
+pub use crate::io::*;
+pub use crate::stream::*;
+#[cfg(feature = "std")]
+pub use crate::tree::*;
\ No newline at end of file
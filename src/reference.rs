@@ -0,0 +1,397 @@
+//! Reference implementation of the GxHash algorithm, written for clarity rather than speed.
+//!
+//! Inputs are processed as plain arrays of bytes, and AES rounds are computed step by step as specified in
+//! FIPS-197, with the S-box derived from the inverses in the AES field rather than copied from the specification.
+//! It shares no code with the optimized implementations, which are tested to produce the same hashes on every
+//! platform.
+//! It is also a starting point for porting the algorithm to other languages.
+//!
+//! It is much slower than the functions at the crate root, and is not meant to be used to actually hash data.
+//!
+//! # Example
+//!
+//! ```
+//! let bytes = [42u8; 1000];
+//! assert_eq!(gxhash::gxhash64(&bytes, 1234), gxhash::reference::gxhash64(&bytes, 1234));
+//! ```
+
+use core::hash::Hasher;
+
+/// A 128-bit block, with the byte at row `r` and column `c` of the AES state at index `4 * c + r`
+type Block = [u8; 16];
+
+const BLOCK_SIZE: usize = 16;
+
+/// Round keys of the algorithm, as three blocks of four little endian u32
+const KEYS: [u32; 12] = [
+    0xF2784542, 0xB09D3E21, 0x89C222E5, 0xFC3BC28E, 0x03FCE279, 0xCB6B2E9B, 0xB361DC58, 0x39132BD9, 0xD0012E32, 0x689D2B7D, 0x5544B1B7, 0xC78B122B,
+];
+
+/// Hashes an arbitrary stream of bytes to an u32, as [`crate::gxhash32`] does
+pub fn gxhash32(input: &[u8], seed: i64) -> u32 {
+    let hash = gxhash(input, seed);
+    u32::from_le_bytes(hash[..4].try_into().unwrap())
+}
+
+/// Hashes an arbitrary stream of bytes to an u64, as [`crate::gxhash64`] does
+pub fn gxhash64(input: &[u8], seed: i64) -> u64 {
+    let hash = gxhash(input, seed);
+    u64::from_le_bytes(hash[..8].try_into().unwrap())
+}
+
+/// Hashes an arbitrary stream of bytes to an u128, as [`crate::gxhash128`] does
+pub fn gxhash128(input: &[u8], seed: i64) -> u128 {
+    u128::from_le_bytes(gxhash(input, seed))
+}
+
+/// A [`Hasher`] producing the same hashes as [`crate::GxHasher`]
+#[derive(Clone, Debug, Default)]
+pub struct GxHasher {
+    state: Block,
+}
+
+impl GxHasher {
+    /// Creates a new hasher using the provided seed, as [`crate::GxHasher::with_seed`] does
+    pub fn with_seed(seed: i64) -> GxHasher {
+        GxHasher {
+            state: splat(&seed.to_le_bytes()),
+        }
+    }
+
+    /// Finish this hasher and return the hashed value as a 128 bit unsigned integer
+    pub fn finish_u128(&self) -> u128 {
+        u128::from_le_bytes(finalize(self.state))
+    }
+
+    fn mix(&mut self, block: Block) {
+        self.state = aes_encrypt_last(block, aes_encrypt(self.state, key(0)));
+    }
+}
+
+// Fixed-size writes are mixed in as a block filled with copies of their little endian bytes
+macro_rules! write {
+    ($name:ident, $type:ty) => {
+        fn $name(&mut self, value: $type) {
+            self.mix(splat(&value.to_le_bytes()));
+        }
+    };
+}
+
+impl Hasher for GxHasher {
+    fn finish(&self) -> u64 {
+        u64::from_le_bytes(finalize(self.state)[..8].try_into().unwrap())
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.mix(compress(bytes));
+    }
+
+    write!(write_u8, u8);
+    write!(write_u16, u16);
+    write!(write_u32, u32);
+    write!(write_u64, u64);
+    write!(write_u128, u128);
+    write!(write_i8, i8);
+    write!(write_i16, i16);
+    write!(write_i32, i32);
+    write!(write_i64, i64);
+    write!(write_i128, i128);
+}
+
+fn gxhash(input: &[u8], seed: i64) -> Block {
+    finalize(aes_encrypt(compress(input), splat(&seed.to_le_bytes())))
+}
+
+fn finalize(hash: Block) -> Block {
+    aes_encrypt_last(aes_encrypt(aes_encrypt(hash, key(0)), key(1)), key(2))
+}
+
+/// Compresses the whole input into a single block
+fn compress(input: &[u8]) -> Block {
+    let len = input.len();
+    if len == 0 {
+        return [0; BLOCK_SIZE];
+    }
+    if len <= BLOCK_SIZE {
+        return partial(input);
+    }
+
+    // The input is split into a first block, partial unless the length is a multiple of the block size,
+    // followed by whole blocks
+    let extra = match len % BLOCK_SIZE {
+        0 => BLOCK_SIZE,
+        extra => extra,
+    };
+    let (first, mut rest) = input.split_at(extra);
+    let mut hash = if extra == BLOCK_SIZE { block(first) } else { partial(first) };
+
+    // Up to three blocks are compressed together, apart from the others
+    let mut v0 = block(&rest[..BLOCK_SIZE]);
+    rest = &rest[BLOCK_SIZE..];
+    for _ in 0..2 {
+        if !rest.is_empty() {
+            v0 = aes_encrypt(v0, block(&rest[..BLOCK_SIZE]));
+            rest = &rest[BLOCK_SIZE..];
+        }
+    }
+
+    if !rest.is_empty() {
+        // The remaining blocks are compressed into the hash one by one, until there is a whole number of groups
+        // of 8 blocks left. Groups are then compressed in two lanes, of the even and odd blocks of each group.
+        let (singles, groups) = rest.split_at(rest.len() % (8 * BLOCK_SIZE));
+        for single in singles.chunks_exact(BLOCK_SIZE) {
+            hash = aes_encrypt(hash, block(single));
+        }
+
+        let (mut lane1, mut lane2) = (hash, hash);
+        let (mut t1, mut t2) = ([0; BLOCK_SIZE], [0; BLOCK_SIZE]);
+        for group in groups.chunks_exact(8 * BLOCK_SIZE) {
+            let v = |i: usize| block(&group[i * BLOCK_SIZE..(i + 1) * BLOCK_SIZE]);
+            let tmp1 = aes_encrypt(aes_encrypt(aes_encrypt(v(0), v(2)), v(4)), v(6));
+            let tmp2 = aes_encrypt(aes_encrypt(aes_encrypt(v(1), v(3)), v(5)), v(7));
+            t1 = add(t1, key(0));
+            t2 = add(t2, key(1));
+            lane1 = aes_encrypt_last(aes_encrypt(tmp1, t1), lane1);
+            lane2 = aes_encrypt_last(aes_encrypt(tmp2, t2), lane2);
+        }
+
+        let len = splat(&(len as u32).to_le_bytes());
+        hash = aes_encrypt(add(lane1, len), add(lane2, len));
+    }
+
+    aes_encrypt_last(hash, aes_encrypt(aes_encrypt(v0, key(0)), key(1)))
+}
+
+/// A block of less than 16 bytes, padded with zeros, to which the length is added
+fn partial(bytes: &[u8]) -> Block {
+    let mut padded = [0; BLOCK_SIZE];
+    padded[..bytes.len()].copy_from_slice(bytes);
+    add(padded, [bytes.len() as u8; BLOCK_SIZE])
+}
+
+fn block(bytes: &[u8]) -> Block {
+    bytes.try_into().unwrap()
+}
+
+fn splat(bytes: &[u8]) -> Block {
+    core::array::from_fn(|i| bytes[i % bytes.len()])
+}
+
+fn key(index: usize) -> Block {
+    core::array::from_fn(|i| KEYS[4 * index + i / 4].to_le_bytes()[i % 4])
+}
+
+/// Byte-wise wrapping addition
+fn add(a: Block, b: Block) -> Block {
+    core::array::from_fn(|i| a[i].wrapping_add(b[i]))
+}
+
+fn xor(a: Block, b: Block) -> Block {
+    core::array::from_fn(|i| a[i] ^ b[i])
+}
+
+/// A full AES round, as performed by the AESENC instruction on x86
+fn aes_encrypt(state: Block, key: Block) -> Block {
+    xor(mix_columns(shift_rows(sub_bytes(state))), key)
+}
+
+/// A last AES round, without MixColumns, as performed by the AESENCLAST instruction on x86
+fn aes_encrypt_last(state: Block, key: Block) -> Block {
+    xor(shift_rows(sub_bytes(state)), key)
+}
+
+fn sub_bytes(state: Block) -> Block {
+    state.map(sbox)
+}
+
+/// Row `r` is rotated left by `r` bytes
+fn shift_rows(state: Block) -> Block {
+    core::array::from_fn(|i| {
+        let (c, r) = (i / 4, i % 4);
+        state[4 * ((c + r) % 4) + r]
+    })
+}
+
+/// Each column is multiplied by the polynomial {03}x^3 + {01}x^2 + {01}x + {02}
+fn mix_columns(state: Block) -> Block {
+    core::array::from_fn(|i| {
+        let (c, r) = (i / 4, i % 4);
+        let a = |row: usize| state[4 * c + (r + row) % 4];
+        gf_mul(a(0), 2) ^ gf_mul(a(1), 3) ^ a(2) ^ a(3)
+    })
+}
+
+fn sbox(byte: u8) -> u8 {
+    SBOX[byte as usize]
+}
+
+/// The multiplicative inverse in the AES field (0 for 0) of each byte, followed by the affine transformation.
+/// Computed at compile time, as it is too slow to be computed for every byte of every round.
+const SBOX: [u8; 256] = {
+    let mut sbox = [0u8; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        // x^254 is the inverse of x, as x^255 = 1 for all non-zero x
+        let mut inverse = 1;
+        let mut i = 0;
+        while i < 254 {
+            inverse = gf_mul(inverse, byte as u8);
+            i += 1;
+        }
+        sbox[byte] = inverse ^ inverse.rotate_left(1) ^ inverse.rotate_left(2) ^ inverse.rotate_left(3) ^ inverse.rotate_left(4) ^ 0x63;
+        byte += 1;
+    }
+    sbox
+};
+
+/// Multiplication in GF(2^8), modulo the polynomial x^8 + x^4 + x^3 + x + 1
+const fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        a = (a << 1) ^ if a & 0x80 != 0 { 0x1b } else { 0 };
+        b >>= 1;
+    }
+    product
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn aes_round_matches_fips_197() {
+        // Inputs and outputs of the first round of the cipher example of FIPS-197, appendix B
+        let state = [0x19, 0x3d, 0xe3, 0xbe, 0xa0, 0xf4, 0xe2, 0x2b, 0x9a, 0xc6, 0x8d, 0x2a, 0xe9, 0xf8, 0x48, 0x08];
+        let key = [0xa0, 0xfa, 0xfe, 0x17, 0x88, 0x54, 0x2c, 0xb1, 0x23, 0xa3, 0x39, 0x39, 0x2a, 0x6c, 0x76, 0x05];
+        let expected = [0xa4, 0x9c, 0x7f, 0xf2, 0x68, 0x9f, 0x35, 0x2b, 0x6b, 0x5b, 0xea, 0x43, 0x02, 0x6a, 0x50, 0x49];
+        assert_eq!(expected, aes_encrypt(state, key));
+
+        assert_eq!(0x63, sbox(0x00));
+        assert_eq!(0xed, sbox(0x53));
+        assert_eq!(0x16, sbox(0xff));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn functions_match_reference() {
+        let mut rng = rand::thread_rng();
+        let mut bytes = [0u8; 1100];
+
+        // Every length up to a few groups of 8 blocks, at every alignment
+        for len in 0..=600 {
+            rng.fill(bytes.as_mut_slice());
+            let offset = len % BLOCK_SIZE;
+            let input = &bytes[offset..offset + len];
+            let seed = rng.gen();
+
+            assert_eq!(gxhash32(input, seed), crate::gxhash32(input, seed), "gxhash32 differs for input of size {len}");
+            assert_eq!(gxhash64(input, seed), crate::gxhash64(input, seed), "gxhash64 differs for input of size {len}");
+            assert_eq!(
+                gxhash128(input, seed),
+                crate::gxhash128(input, seed),
+                "gxhash128 differs for input of size {len}"
+            );
+        }
+
+        // Random lengths, alignments and seeds
+        for _ in 0..200 {
+            rng.fill(bytes.as_mut_slice());
+            let offset = rng.gen_range(0..64);
+            let input = &bytes[offset..offset + rng.gen_range(0..1024)];
+            let seed = rng.gen();
+
+            assert_eq!(
+                gxhash128(input, seed),
+                crate::gxhash128(input, seed),
+                "gxhash128 differs for input of size {}",
+                input.len()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn hasher_matches_reference() {
+        let mut rng = rand::thread_rng();
+        let mut bytes = [0u8; 300];
+
+        for _ in 0..200 {
+            let seed = rng.gen();
+            let mut hasher = crate::GxHasher::with_seed(seed);
+            let mut reference = GxHasher::with_seed(seed);
+
+            macro_rules! write {
+                ($($i:literal => $name:ident),*) => {
+                    match rng.gen_range(0..12) {
+                        $($i => {
+                            let value = rng.gen();
+                            hasher.$name(value);
+                            reference.$name(value);
+                        })*
+                        _ => {
+                            rng.fill(bytes.as_mut_slice());
+                            let offset = rng.gen_range(0..BLOCK_SIZE);
+                            let input = &bytes[offset..offset + rng.gen_range(0..256)];
+                            hasher.write(input);
+                            reference.write(input);
+                        }
+                    }
+                };
+            }
+            for _ in 0..rng.gen_range(0..5) {
+                write!(0 => write_u8, 1 => write_u16, 2 => write_u32, 3 => write_u64, 4 => write_u128,
+                    5 => write_i8, 6 => write_i16, 7 => write_i32, 8 => write_i64, 9 => write_i128);
+            }
+
+            assert_eq!(reference.finish(), hasher.finish());
+            assert_eq!(reference.finish_u128(), hasher.finish_u128());
+        }
+
+        assert_eq!(GxHasher::default().finish(), crate::GxHasher::default().finish());
+    }
+
+    // Every line of the published vectors, see the header of the file for its format
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn reference_matches_published_vectors() {
+        let input: Vec<u8> = (0..1024).map(|i| (i * 31 + 7) as u8).collect();
+        let hex = |hash: u128, width: usize| format!("{hash:0width$x}");
+        let mut lines = 0;
+
+        for line in include_str!("../vectors/v3.txt").lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let seed: i64 = fields[1].parse().unwrap();
+            let mut hasher = GxHasher::with_seed(seed);
+
+            macro_rules! write {
+                ($($name:ident: $type:ty),*) => {
+                    match fields[0] {
+                        "gxhash" => {
+                            let input = &input[..fields[2].parse::<usize>().unwrap()];
+                            let hashes = [hex(gxhash32(input, seed) as u128, 8), hex(gxhash64(input, seed) as u128, 16), hex(gxhash128(input, seed), 32)];
+                            assert_eq!(fields[3..], hashes, "{line}");
+                            lines += 1;
+                            continue;
+                        }
+                        "write" => hasher.write(&input[..fields[2].parse::<usize>().unwrap()]),
+                        $(stringify!($name) => hasher.$name(fields[2].parse::<$type>().unwrap()),)*
+                        kind => panic!("unknown vector kind {kind}"),
+                    }
+                };
+            }
+            write!(write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+                write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128);
+
+            assert_eq!(fields[3..], [hex(hasher.finish() as u128, 16), hex(hasher.finish_u128(), 32)], "{line}");
+            lines += 1;
+        }
+
+        assert_eq!(4 * 1025 + 4 * 129 + 4 * (5 * 5 + 5 * 6), lines);
+    }
+}
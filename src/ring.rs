@@ -0,0 +1,312 @@
+//! Consistent hashing, assigning keys to nodes that join and leave, such as the servers of a cache cluster.
+//!
+//! Each node is placed at many points of a ring of 64-bit hashes, its virtual nodes, and each key goes to the
+//! node of the first point following its hash on the ring. Adding a node only moves to it the keys that now fall
+//! before its points, and removing a node only moves its own keys, to the nodes following its points, so that
+//! most keys stay in place as the cluster changes. Virtual nodes spread the keys of a node all around the ring,
+//! which evens out the load of the nodes.
+//!
+//! Points and keys are hashed with [`GxHasher::with_seed`], so rings with the same seed and the same nodes
+//! locate keys the same way in every process, whatever the order the nodes were added in. This also holds across
+//! platforms for nodes and keys whose [`Hash`] implementation writes the same bytes on every platform, such as
+//! strings and fixed-width integers, but not `usize` or `isize`, nor slices and vectors, whose length is written
+//! as a `usize`: rings shared between 32-bit and 64-bit platforms should key on strings or fixed-width integers.
+//!
+//! # Example
+//!
+//! ```
+//! use gxhash::ring::HashRing;
+//!
+//! let mut ring = HashRing::new();
+//! ring.add_node("cache-a", 1);
+//! ring.add_node("cache-b", 1);
+//! ring.add_node("cache-c", 2);
+//!
+//! let node = ring.locate(&"user:1234").unwrap();
+//! let replicas = ring.locate_n(&"user:1234", 2);
+//! assert_eq!(node, replicas[0]);
+//! ```
+
+use core::hash::{Hash, Hasher};
+
+use crate::GxHasher;
+
+/// Number of virtual nodes of a node for each unit of its weight
+pub const VIRTUAL_NODES_PER_WEIGHT: u32 = 160;
+
+/// A consistent hashing ring of nodes of type `N`, with virtual nodes placed by hashing the nodes with
+/// [`GxHasher`]. See the [module documentation](self).
+#[derive(Clone, Debug)]
+pub struct HashRing<N> {
+    seed: i64,
+    nodes: Vec<(N, u32)>,
+    // Points of the virtual nodes, sorted, with the index of their node
+    points: Vec<(u64, usize)>,
+}
+
+impl<N: Hash + Eq> HashRing<N> {
+    /// Creates an empty ring, with a seed of 0
+    #[inline]
+    pub fn new() -> HashRing<N> {
+        HashRing::with_seed(0)
+    }
+
+    /// Creates an empty ring using the provided seed. Rings only locate keys the same way when they have the
+    /// same seed.
+    #[inline]
+    pub fn with_seed(seed: i64) -> HashRing<N> {
+        HashRing {
+            seed,
+            nodes: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+
+    /// Adds a node, with [`VIRTUAL_NODES_PER_WEIGHT`] virtual nodes for each unit of weight, so that nodes get
+    /// shares of the keys proportional to their weights. A node with a weight of 0 gets no keys.
+    /// If the node is already in the ring, its weight is updated.
+    pub fn add_node(&mut self, node: N, weight: u32) {
+        self.remove_node(&node);
+        let index = self.nodes.len();
+        let virtual_nodes = weight.saturating_mul(VIRTUAL_NODES_PER_WEIGHT);
+        let points: Vec<(u64, usize)> = (0..virtual_nodes).map(|i| (self.point(&node, i), index)).collect();
+        self.points.extend(points);
+        self.nodes.push((node, weight));
+        // Points of different nodes only collide with a negligible probability, so their order doesn't depend
+        // on the order nodes were added in
+        self.points.sort_unstable_by_key(|&(point, _)| point);
+    }
+
+    /// Removes a node, returning its weight, or `None` if it isn't in the ring
+    pub fn remove_node(&mut self, node: &N) -> Option<u32> {
+        let index = self.nodes.iter().position(|(n, _)| n == node)?;
+        let (_, weight) = self.nodes.swap_remove(index);
+        // The last node takes the place of the removed one
+        let moved = self.nodes.len();
+        self.points.retain(|&(_, i)| i != index);
+        for (_, i) in self.points.iter_mut() {
+            if *i == moved {
+                *i = index;
+            }
+        }
+        Some(weight)
+    }
+
+    /// Node of a key, or `None` if the ring has no nodes with a non-zero weight
+    #[inline]
+    pub fn locate<K: Hash + ?Sized>(&self, key: &K) -> Option<&N> {
+        self.walk(key).next()
+    }
+
+    /// Up to `n` distinct nodes for a key, such as the nodes holding its replicas: the node [`HashRing::locate`]
+    /// returns, followed by the next distinct nodes on the ring. Fewer nodes are returned if the ring has less
+    /// than `n` nodes with a non-zero weight.
+    pub fn locate_n<K: Hash + ?Sized>(&self, key: &K, n: usize) -> Vec<&N> {
+        let mut nodes: Vec<&N> = Vec::with_capacity(n.min(self.nodes.len()));
+        let mut seen = vec![false; self.nodes.len()];
+        for index in self.walk_indices(key) {
+            if nodes.len() == n {
+                break;
+            }
+            if !seen[index] {
+                seen[index] = true;
+                nodes.push(&self.nodes[index].0);
+            }
+        }
+        nodes
+    }
+
+    /// Weight of a node, or `None` if it isn't in the ring
+    #[inline]
+    pub fn weight(&self, node: &N) -> Option<u32> {
+        self.nodes.iter().find(|(n, _)| n == node).map(|&(_, weight)| weight)
+    }
+
+    /// Iterator over the nodes of the ring and their weights, in no particular order
+    #[inline]
+    pub fn nodes(&self) -> impl Iterator<Item = (&N, u32)> {
+        self.nodes.iter().map(|(node, weight)| (node, *weight))
+    }
+
+    /// Number of nodes in the ring
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the ring has no nodes
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    #[inline]
+    fn point(&self, node: &N, virtual_node: u32) -> u64 {
+        let mut hasher = GxHasher::with_seed(self.seed);
+        node.hash(&mut hasher);
+        hasher.write_u32(virtual_node);
+        hasher.finish()
+    }
+
+    // Indices of the nodes of the points following the hash of a key, around the whole ring
+    fn walk_indices<K: Hash + ?Sized>(&self, key: &K) -> impl Iterator<Item = usize> + '_ {
+        let mut hasher = GxHasher::with_seed(self.seed);
+        key.hash(&mut hasher);
+        let start = self.points.partition_point(|&(point, _)| point < hasher.finish());
+        self.points[start..].iter().chain(&self.points[..start]).map(|&(_, index)| index)
+    }
+
+    #[inline]
+    fn walk<K: Hash + ?Sized>(&self, key: &K) -> impl Iterator<Item = &N> {
+        self.walk_indices(key).map(|index| &self.nodes[index].0)
+    }
+}
+
+impl<N: Hash + Eq> Default for HashRing<N> {
+    #[inline]
+    fn default() -> HashRing<N> {
+        HashRing::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const KEYS: u32 = 100_000;
+
+    fn ring(nodes: impl IntoIterator<Item = u32>) -> HashRing<String> {
+        let mut ring = HashRing::new();
+        for node in nodes {
+            ring.add_node(format!("node-{node}"), 1);
+        }
+        ring
+    }
+
+    fn locations(ring: &HashRing<String>) -> Vec<String> {
+        (0..KEYS).map(|key| ring.locate(&key).unwrap().clone()).collect()
+    }
+
+    #[test]
+    fn empty_ring_locates_nothing() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert_eq!(None, ring.locate(&42));
+        assert!(ring.locate_n(&42, 3).is_empty());
+
+        ring.add_node("idle", 0);
+        assert_eq!(None, ring.locate(&42));
+        assert_eq!(1, ring.len());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn load_is_spread_evenly() {
+        let ring = ring(0..10);
+        let mut counts = std::collections::HashMap::new();
+        for node in locations(&ring) {
+            *counts.entry(node).or_insert(0usize) += 1;
+        }
+        assert_eq!(10, counts.len());
+        // With 160 virtual nodes per node, loads are typically within 10% of the mean
+        let mean = KEYS as f64 / 10.0;
+        for (node, count) in counts {
+            assert!((count as f64 - mean).abs() < 0.25 * mean, "{node} has {count} keys");
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn load_follows_weights() {
+        let mut ring = HashRing::new();
+        ring.add_node("small", 1);
+        ring.add_node("large", 3);
+        let large = (0..KEYS).filter(|key| ring.locate(key) == Some(&"large")).count();
+        let share = large as f64 / KEYS as f64;
+        assert!((share - 0.75).abs() < 0.05, "large node has a share of {share}");
+        assert_eq!(Some(3), ring.weight(&"large"));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn adding_node_moves_keys_to_it_only() {
+        let before = locations(&ring(0..10));
+        let after = locations(&ring(0..11));
+        let mut moved = 0;
+        for (before, after) in before.iter().zip(&after) {
+            if before != after {
+                assert_eq!("node-10", after);
+                moved += 1;
+            }
+        }
+        // About the share of the new node, 1/11 of the keys
+        let share = moved as f64 / KEYS as f64;
+        assert!((share - 1.0 / 11.0).abs() < 0.03, "{share} of the keys moved");
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn removing_node_moves_its_keys_only() {
+        let mut ring = ring(0..10);
+        let before = locations(&ring);
+        assert_eq!(Some(1), ring.remove_node(&"node-3".to_string()));
+        assert_eq!(None, ring.remove_node(&"node-3".to_string()));
+        let after = locations(&ring);
+
+        let mut moved = 0;
+        for (before, after) in before.iter().zip(&after) {
+            if before != after {
+                assert_eq!("node-3", before);
+                moved += 1;
+            }
+        }
+        assert_eq!(before.iter().filter(|&node| node == "node-3").count(), moved);
+        assert_eq!(9, ring.len());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn order_of_nodes_does_not_matter() {
+        let forward = ring(0..10);
+        let backward = ring((0..10).rev());
+        let mut shuffled = ring([0, 1, 2, 3, 42, 4, 5, 6, 7, 8, 9]);
+        shuffled.remove_node(&"node-42".to_string());
+        assert_eq!(locations(&forward), locations(&backward));
+        assert_eq!(locations(&forward), locations(&shuffled));
+        assert_ne!(
+            locations(&forward),
+            locations(&{
+                let mut ring = HashRing::with_seed(1);
+                (0..10).for_each(|node| ring.add_node(format!("node-{node}"), 1));
+                ring
+            })
+        );
+    }
+
+    #[test]
+    fn replicas_are_distinct_nodes() {
+        let ring = ring(0..5);
+        for key in 0..1000 {
+            let replicas = ring.locate_n(&key, 3);
+            assert_eq!(3, replicas.len());
+            assert_eq!(ring.locate(&key), Some(replicas[0]));
+            assert!(replicas[0] != replicas[1] && replicas[1] != replicas[2] && replicas[0] != replicas[2]);
+            assert_eq!(5, ring.locate_n(&key, 10).len());
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn replicas_move_little() {
+        // Removing a node only changes the replica sets it was part of
+        let mut ring = ring(0..10);
+        let before: Vec<Vec<String>> = (0..10_000u32).map(|key| ring.locate_n(&key, 3).into_iter().cloned().collect()).collect();
+        ring.remove_node(&"node-7".to_string());
+        for (key, before) in (0..10_000u32).zip(&before) {
+            let after: Vec<String> = ring.locate_n(&key, 3).into_iter().cloned().collect();
+            if !before.contains(&"node-7".to_string()) {
+                assert_eq!(before, &after);
+            }
+        }
+    }
+}
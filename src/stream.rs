@@ -0,0 +1,246 @@
+use core::hash::Hasher;
+
+use crate::gxhash::platform::*;
+use crate::gxhash::*;
+
+/// A [`Hasher`] for hashing a stream of bytes written in several pieces. Unlike [`GxHasher`], which mixes
+/// every write into its state, the hashes it produces are the same as [`gxhash32`], [`gxhash64`] and
+/// [`gxhash128`] on the concatenated input, whatever the way the input was split.
+///
+/// GxHash lays out the vectors it reads relatively to the total length of the input, so the input can only
+/// be compressed as it comes when that length is known upfront (see [`GxStreamHasher::with_seed_and_len`]).
+/// In that case, at most one block of 128 bytes is buffered between writes.
+/// Otherwise, the whole input is buffered until the hash is requested, which requires the 'std' feature.
+///
+/// # Example
+///
+/// ```
+/// use core::hash::Hasher;
+/// use gxhash::GxStreamHasher;
+///
+/// let mut hasher = GxStreamHasher::with_seed_and_len(1234, 10);
+///
+/// hasher.write(b"hello");
+/// hasher.write(b"world");
+///
+/// assert_eq!(hasher.finish(), gxhash::gxhash64(b"helloworld", 1234));
+/// ```
+///
+/// [`GxHasher`]: crate::GxHasher
+#[derive(Clone, Debug)]
+pub struct GxStreamHasher {
+    seed: State,
+    input: Input,
+}
+
+// Boxing the blocks would cost an allocation per hasher, which the sized mode is all about avoiding
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+enum Input {
+    #[cfg(feature = "std")]
+    Buffered(Vec<u8>),
+    Sized(Blocks),
+}
+
+impl GxStreamHasher {
+    /// Creates a new streaming hasher using the provided seed, for an input of unknown length.
+    /// The whole input is buffered until the hash is requested.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn with_seed(seed: i64) -> GxStreamHasher {
+        GxStreamHasher {
+            seed: unsafe { create_seed(seed) },
+            input: Input::Buffered(Vec::new()),
+        }
+    }
+
+    /// Creates a new streaming hasher using the provided seed, for an input of exactly `len` bytes.
+    /// The input is compressed as it is written.
+    ///
+    /// # Panics
+    /// Writing more than `len` bytes panics, as does [`GxStreamHasher::finish_u128`] before `len` bytes were
+    /// written. [`Hasher::finish`] doesn't panic, but its hash is unspecified until then, which
+    /// [`GxStreamHasher::try_finish_u128`] allows to check beforehand.
+    #[inline]
+    pub fn with_seed_and_len(seed: i64, len: usize) -> GxStreamHasher {
+        GxStreamHasher {
+            seed: unsafe { create_seed(seed) },
+            input: Input::Sized(Blocks::new(len)),
+        }
+    }
+
+    /// Finish this hasher and return the hashed value as a 128 bit
+    /// unsigned integer.
+    ///
+    /// # Panics
+    /// Panics if the hasher was created with [`GxStreamHasher::with_seed_and_len`] and fewer bytes were written.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        self.try_finish_u128().expect("hash requested before the announced length was written")
+    }
+
+    /// Same as [`GxStreamHasher::finish_u128`], returning `None` instead of panicking if the hasher was created
+    /// with [`GxStreamHasher::with_seed_and_len`] and fewer bytes were written.
+    #[inline]
+    pub fn try_finish_u128(&self) -> Option<u128> {
+        let complete = match &self.input {
+            #[cfg(feature = "std")]
+            Input::Buffered(_) => true,
+            Input::Sized(blocks) => blocks.is_complete(),
+        };
+        if !complete {
+            return None;
+        }
+        unsafe {
+            let p = &self.digest() as *const State as *const u128;
+            Some(u128::from_le(*p))
+        }
+    }
+
+    #[inline]
+    fn digest(&self) -> State {
+        unsafe {
+            match &self.input {
+                #[cfg(feature = "std")]
+                Input::Buffered(bytes) => gxhash(bytes, self.seed),
+                Input::Sized(blocks) => stream_digest(blocks, self.seed),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for GxStreamHasher {
+    /// Creates a new streaming hasher with a empty seed, for an input of unknown length.
+    #[inline]
+    fn default() -> GxStreamHasher {
+        GxStreamHasher::with_seed(0)
+    }
+}
+
+impl Hasher for GxStreamHasher {
+    /// Unlike [`GxStreamHasher::finish_u128`], doesn't panic if fewer bytes than announced were written, in which
+    /// case the hash is unspecified.
+    #[inline]
+    fn finish(&self) -> u64 {
+        unsafe {
+            let p = &self.digest() as *const State as *const u64;
+            u64::from_le(*p)
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.input {
+            #[cfg(feature = "std")]
+            Input::Buffered(buffer) => buffer.extend_from_slice(bytes),
+            Input::Sized(blocks) => unsafe { stream_write(blocks, bytes) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::Rng;
+
+    use super::*;
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        rand::thread_rng().fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stream_matches_oneshot_for_every_split() {
+        for len in (0..600).chain([1023, 1024, 1025, 4242]) {
+            let bytes = random_bytes(len);
+            let expected = gxhash128(&bytes, 42);
+
+            for split in 0..=len {
+                let mut hasher = GxStreamHasher::with_seed_and_len(42, len);
+                hasher.write(&bytes[..split]);
+                hasher.write(&bytes[split..]);
+                assert_eq!(expected, hasher.finish_u128(), "sized stream differs for input of size {len} split at {split}");
+
+                #[cfg(feature = "std")]
+                {
+                    let mut hasher = GxStreamHasher::with_seed(42);
+                    hasher.write(&bytes[..split]);
+                    hasher.write(&bytes[split..]);
+                    assert_eq!(
+                        expected,
+                        hasher.finish_u128(),
+                        "buffered stream differs for input of size {len} split at {split}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn stream_matches_oneshot_for_random_splits() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..200 {
+            let len = rng.gen_range(0..5000);
+            let bytes = random_bytes(len);
+            let seed = rng.gen::<i64>();
+
+            let mut hasher = GxStreamHasher::with_seed_and_len(seed, len);
+            let mut remaining = bytes.as_slice();
+            while !remaining.is_empty() {
+                let (chunk, rest) = remaining.split_at(rng.gen_range(0..=remaining.len().min(300)));
+                hasher.write(chunk);
+                remaining = rest;
+            }
+
+            assert_eq!(gxhash32(&bytes, seed), hasher.finish() as u32);
+            assert_eq!(gxhash64(&bytes, seed), hasher.finish());
+            assert_eq!(gxhash128(&bytes, seed), hasher.finish_u128());
+        }
+    }
+
+    #[test]
+    fn stream_matches_oneshot_for_byte_by_byte_writes() {
+        let bytes = random_bytes(1200);
+        for len in [0, 1, 15, 16, 17, 64, 65, 127, 128, 129, 200, 1200] {
+            let mut hasher = GxStreamHasher::with_seed_and_len(7, len);
+            for byte in &bytes[..len] {
+                hasher.write_u8(*byte);
+            }
+            assert_eq!(gxhash64(&bytes[..len], 7), hasher.finish(), "stream differs for input of size {len}");
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn stream_rejects_extra_bytes() {
+        let mut hasher = GxStreamHasher::with_seed_and_len(0, 4);
+        hasher.write(b"hello");
+    }
+
+    #[test]
+    #[should_panic]
+    fn stream_rejects_missing_bytes() {
+        let mut hasher = GxStreamHasher::with_seed_and_len(0, 10);
+        hasher.write(b"hello");
+        let _ = hasher.finish_u128();
+    }
+
+    #[test]
+    fn stream_tells_missing_bytes() {
+        for len in [10, 1000] {
+            let mut hasher = GxStreamHasher::with_seed_and_len(0, len);
+            hasher.write(b"hello");
+            assert_eq!(None, hasher.try_finish_u128());
+            // Unspecified, but not a panic, as hashers are used in generic code
+            let _ = hasher.finish();
+
+            hasher.write(&vec![0; len - 5]);
+            assert_eq!(Some(gxhash128(&[b"hello".as_slice(), &vec![0; len - 5]].concat(), 0)), hasher.try_finish_u128());
+        }
+    }
+}
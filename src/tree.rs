@@ -0,0 +1,184 @@
+use core::hash::Hasher;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::{gxhash128, GxStreamHasher};
+
+/// Version of the tree hashing mode, which is part of every tree hash. Any change to the way the tree is built,
+/// including the [`TREE_CHUNK_SIZE`], comes with a new version, so that tree hashes of different versions never
+/// get mistaken for one another.
+pub const TREE_VERSION: u32 = 1;
+
+/// Size of the chunks the input is split into by the tree hashing mode. The last chunk may be smaller.
+pub const TREE_CHUNK_SIZE: usize = 1 << 20;
+
+// Prefixes of the inputs of the nodes, telling chunks, inner nodes and the root apart
+const LEAF_PREFIX: u8 = 0;
+const PARENT_PREFIX: u8 = 1;
+const ROOT_PREFIX: u8 = 2;
+
+/// Hashes an arbitrary stream of bytes to an u128, using as many threads as there are available cores.
+/// Unlike [`gxhash128`], which processes its input on a single core, the input is split into chunks of
+/// [`TREE_CHUNK_SIZE`] bytes which are hashed in parallel, so that hashing very large inputs scales with cores.
+///
+/// Tree hashes are not the same as the ones of [`gxhash128`], but they only depend on the input and the seed,
+/// whatever the number of threads. They are defined as follows, for version 1 (see [`TREE_VERSION`]):
+/// - Each chunk is hashed with [`gxhash128`], from the byte `[0]` followed by the chunk. Empty inputs have a
+///   single, empty, chunk.
+/// - Pairs of consecutive hashes are hashed with [`gxhash128`] into their parent, from the bytes `[1]`
+///   followed by the little endian bytes of both hashes. If there is an odd number of hashes, the last one is
+///   carried over to the next level as is. This is repeated until a single hash remains.
+/// - The tree hash is the [`gxhash128`] of the bytes `[2]`, followed by the little endian bytes of the version,
+///   of the input length as an u64, and of the remaining hash.
+///
+/// All hashes use the given seed.
+///
+/// # Example
+///
+/// ```
+/// let bytes = vec![42u8; 10_000_000];
+/// let seed = 1234;
+/// assert_eq!(gxhash::gxhash128_tree(&bytes, seed), gxhash::gxhash128_tree_with_threads(&bytes, seed, 3));
+/// ```
+pub fn gxhash128_tree(input: &[u8], seed: i64) -> u128 {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    gxhash128_tree_with_threads(input, seed, threads)
+}
+
+/// Same as [`gxhash128_tree`], using at most the given number of threads. With a single thread (or none), the
+/// input is hashed on the calling thread.
+pub fn gxhash128_tree_with_threads(input: &[u8], seed: i64, threads: usize) -> u128 {
+    let chunks_count = input.len().div_ceil(TREE_CHUNK_SIZE).max(1);
+    let mut hashes = vec![0u128; chunks_count];
+
+    // Each thread hashes a contiguous range of chunks
+    let chunks_per_thread = chunks_count.div_ceil(threads.max(1));
+    if chunks_per_thread == chunks_count {
+        hash_chunks(input, seed, &mut hashes);
+    } else {
+        thread::scope(|scope| {
+            let inputs = input.chunks(chunks_per_thread * TREE_CHUNK_SIZE);
+            for (input, hashes) in inputs.zip(hashes.chunks_mut(chunks_per_thread)) {
+                scope.spawn(move || hash_chunks(input, seed, hashes));
+            }
+        });
+    }
+
+    // Inner nodes are cheap compared to the chunks, they are hashed on the calling thread
+    while hashes.len() > 1 {
+        hashes = hashes
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_parent(*left, *right, seed),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    hash_root(hashes[0], input.len(), seed)
+}
+
+fn hash_chunks(input: &[u8], seed: i64, hashes: &mut [u128]) {
+    if input.is_empty() {
+        hashes[0] = hash_leaf(input, seed);
+        return;
+    }
+    for (chunk, hash) in input.chunks(TREE_CHUNK_SIZE).zip(hashes) {
+        *hash = hash_leaf(chunk, seed);
+    }
+}
+
+// Streamed, so that the prefix doesn't require copying the chunk
+fn hash_leaf(chunk: &[u8], seed: i64) -> u128 {
+    let mut hasher = GxStreamHasher::with_seed_and_len(seed, chunk.len() + 1);
+    hasher.write(&[LEAF_PREFIX]);
+    hasher.write(chunk);
+    hasher.finish_u128()
+}
+
+fn hash_parent(left: u128, right: u128, seed: i64) -> u128 {
+    let mut bytes = [0u8; 33];
+    bytes[0] = PARENT_PREFIX;
+    bytes[1..17].copy_from_slice(&left.to_le_bytes());
+    bytes[17..].copy_from_slice(&right.to_le_bytes());
+    gxhash128(&bytes, seed)
+}
+
+fn hash_root(hash: u128, len: usize, seed: i64) -> u128 {
+    let mut bytes = [0u8; 29];
+    bytes[0] = ROOT_PREFIX;
+    bytes[1..5].copy_from_slice(&TREE_VERSION.to_le_bytes());
+    bytes[5..13].copy_from_slice(&(len as u64).to_le_bytes());
+    bytes[13..].copy_from_slice(&hash.to_le_bytes());
+    gxhash128(&bytes, seed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use rand::Rng;
+
+    use super::*;
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        rand::thread_rng().fill(bytes.as_mut_slice());
+        bytes
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn tree_hash_is_independent_of_threads() {
+        let bytes = random_bytes(5 * TREE_CHUNK_SIZE + 7);
+        for len in [0, 1, TREE_CHUNK_SIZE - 1, TREE_CHUNK_SIZE, TREE_CHUNK_SIZE + 1, 3 * TREE_CHUNK_SIZE, bytes.len()] {
+            let expected = gxhash128_tree_with_threads(&bytes[..len], 42, 1);
+            for threads in [0, 2, 3, 4, 16] {
+                assert_eq!(
+                    expected,
+                    gxhash128_tree_with_threads(&bytes[..len], 42, threads),
+                    "tree hash differs for input of size {len} with {threads} threads"
+                );
+            }
+            assert_eq!(expected, gxhash128_tree(&bytes[..len], 42));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn tree_hash_follows_definition() {
+        let bytes = random_bytes(2 * TREE_CHUNK_SIZE + 100);
+        let (chunk1, chunk2, chunk3) = (
+            &bytes[..TREE_CHUNK_SIZE],
+            &bytes[TREE_CHUNK_SIZE..2 * TREE_CHUNK_SIZE],
+            &bytes[2 * TREE_CHUNK_SIZE..],
+        );
+
+        let leaf = |chunk: &[u8]| gxhash128(&[&[0], chunk].concat(), 7);
+
+        let left = hash_parent(leaf(chunk1), leaf(chunk2), 7);
+        let top = hash_parent(left, leaf(chunk3), 7);
+        assert_eq!(hash_root(top, bytes.len(), 7), gxhash128_tree_with_threads(&bytes, 7, 2));
+
+        assert_eq!(hash_root(leaf(&[]), 0, 7), gxhash128_tree(&[], 7));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn every_chunk_mutates_tree_hash() {
+        let mut bytes = vec![42u8; 4 * TREE_CHUNK_SIZE + 1];
+        let ref_hash = gxhash128_tree(&bytes, 0);
+        for i in (0..bytes.len()).step_by(TREE_CHUNK_SIZE) {
+            bytes[i] ^= 1;
+            assert_ne!(ref_hash, gxhash128_tree(&bytes, 0), "chunk at {i} not processed");
+            bytes[i] ^= 1;
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn tree_is_stable() {
+        assert_eq!(228005942012279325048601117193882535301, gxhash128_tree(&[0u8; 0], 0));
+        assert_eq!(9295396770796878914399225601248933767, gxhash128_tree(&vec![42u8; 3 * TREE_CHUNK_SIZE + 5], 42));
+    }
+}
@@ -0,0 +1,325 @@
+//! Version 3 of the GxHash algorithm.
+//!
+//! Hashes of the crate root functions and types may change with every major version of the crate, which is a
+//! problem for hashes that outlive a process, such as hashes persisted on disk or sent over the network.
+//! This module names the version 3 of the algorithm, so that code persisting hashes can state which version it
+//! relies on. Once the algorithm changes, the new version lands next to this one in its own module (such as `v4`),
+//! and the crate root moves to it, so that persisted hashes can be migrated while reading both versions.
+//!
+//! For now, version 3 is the current version, and the items of this module are re-exports of those of the crate
+//! root rather than a copy of their own. What keeps them computing version 3 hashes is a test checking them against
+//! the [published vectors](https://github.com/ogxd/gxhash/tree/main/vectors) of the version and against
+//! [`crate::reference`] on every platform, which a release of the crate must pass.
+//!
+//! # Example
+//!
+//! ```
+//! use gxhash::v3;
+//!
+//! let bytes = [42u8; 1000];
+//! let seed = 1234;
+//! // Hashes of this module are checked against the published version 3 vectors
+//! println!("Persisted hash is {:x}!", v3::gxhash64(&bytes, seed));
+//! ```
+
+// Version 3 is the current version of the algorithm, so its items are the ones of the crate root, which saves
+// maintaining two copies of every platform implementation. This only holds until the hashes of the crate root
+// change: before any change breaking the vectors below, the algorithm and the hasher MUST be copied into this
+// module (as a `v3` directory of their own), and the re-exports below replaced by the copy. The vectors are
+// never to be updated to follow the crate root.
+pub use crate::gxhash::{gxhash128, gxhash32, gxhash64};
+pub use crate::hasher::{GxBuildHasher, GxHasher};
+
+#[cfg(test)]
+mod tests {
+
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    use super::*;
+
+    // (input length, seed, gxhash32, gxhash64, gxhash128), for lengths around every vector and block boundary
+    #[rustfmt::skip]
+    const GOLDEN: &[(usize, i64, u32, u64, u128)] = &[
+        (0, 42, 712240684, 1387850744621952556, 176273285664095510864300293959886040620),
+        (0, -1, 1112801836, 16771408539608678956, 248419264395047049178498148452072161836),
+        (0, 9223372036854775807, 3319239482, 1257719341308678970, 138609569025856388842807312781508318010),
+        (1, 0, 2626540633, 11866436480409654361, 305029718118973824359817238224955102297),
+        (1, 42, 1005481647, 2691759462580907695, 215566346268826411912608914644614934191),
+        (1, -1, 3210209793, 5002567559272720897, 105470248486900114039954118054090631681),
+        (1, 9223372036854775807, 3275602241, 9053170195656194369, 320427023388619466424414183803701803329),
+        (3, 0, 1693024510, 318860065965179134, 261854408981095486843963709536871219454),
+        (3, 42, 3732889216, 580565104093781632, 197998044568406258217552210613308972672),
+        (3, -1, 2106616428, 16051849188917406316, 257036557153510598646323310609904462444),
+        (3, 9223372036854775807, 1716285550, 15648059285597614190, 93212348593281895999262897326470754414),
+        (15, 0, 3195063046, 17339691929665850118, 310531607877131899063368171545164827398),
+        (15, 42, 3703530960, 1891504704668526032, 38049937546335004381081861387376879056),
+        (15, -1, 2212871865, 3919161293355204281, 302055704317930317446965866573762772665),
+        (15, 9223372036854775807, 493673712, 14200160031469264112, 291232289749354132741897115486451981552),
+        (16, 0, 4079941034, 7039205515346245034, 258997385546662872289827258870413587882),
+        (16, 42, 3804716607, 13429856189953826367, 281679071208165279856952178154251181631),
+        (16, -1, 4220678969, 7891610035293418297, 195397741211271273394426575894342693689),
+        (16, 9223372036854775807, 687408529, 12655988120449779089, 49112354866559033531856580814520780177),
+        (17, 0, 4076799340, 17737932478032577900, 242721969472113242334935045600628901228),
+        (17, 42, 2320940517, 9088795857500159461, 327692733210134152751275505313467776485),
+        (17, -1, 2087011902, 11585343321457116734, 29684293373214741391873036656046327358),
+        (17, 9223372036854775807, 2262379661, 18293243289137654925, 112129703817204590485397348563805613197),
+        (31, 0, 801545331, 925399486963293299, 194911565020853010414414890172703743091),
+        (31, 42, 1580258058, 16959712314493293322, 282654880487007497682912746351348470538),
+        (31, -1, 4254031314, 13174374527566568914, 169222417536171341969062170992946666962),
+        (31, 9223372036854775807, 51734054, 7136578802239759910, 179491047513464580843237837108025845286),
+        (32, 0, 3534496481, 13578600948777624289, 53328873189616343233577327473487258337),
+        (32, 42, 4079887471, 9080253835027293295, 226380466843327523129821000396098643055),
+        (32, -1, 4135736543, 15640067028189074655, 155706786183312837561727577440158635231),
+        (32, 9223372036854775807, 3925833570, 15029037952221017954, 54065229164972433232748905052868933474),
+        (33, 0, 9603421, 12144553747855608157, 230724529739470856004123225624339974493),
+        (33, 42, 3934816815, 18125805060222388783, 52712162160059746130838003453937617455),
+        (33, -1, 434721807, 5081070509172020239, 300261940668377037310832042894837765135),
+        (33, 9223372036854775807, 1336448568, 14960545255758796344, 8559835808165227008030998298985272888),
+        (63, 0, 1613733163, 17508211588487683371, 164248885554629145754402092559201443115),
+        (63, 42, 2493462782, 16186724644300994814, 313278248199806499961942806318704179454),
+        (63, -1, 2847835953, 8622673996673877809, 35247847523574497014966954761878669105),
+        (63, 9223372036854775807, 247095518, 5882149417984352478, 81925776256898444595765617726836072670),
+        (64, 0, 4122922275, 12233669444278078755, 291256251771096907068366576484779740451),
+        (64, 42, 1522119296, 9076633810694156928, 240692321444506709175810230627499356800),
+        (64, -1, 372527565, 10127133946303238605, 225270787034898368395067273221508452813),
+        (64, 9223372036854775807, 176578663, 5946845820258508903, 84552581312433261822026253663069429863),
+        (65, 0, 3391372684, 7656693495379541388, 169895470487418527979924919448963923340),
+        (65, 42, 4010507437, 15236770938999241901, 249680918677862784351038100059978431661),
+        (65, -1, 1073206018, 4810382141600289538, 165799592846073726572294933270855406338),
+        (65, 9223372036854775807, 3085270429, 14360121718723673501, 197620633802793051232653442770662686109),
+        (127, 0, 333776264, 3414319393711392136, 35743974261040086304972449759071896968),
+        (127, 42, 265411606, 11414123310214142998, 256872557641475412634207397744458456086),
+        (127, -1, 728518566, 6632236336833908646, 37539519886297910415809514838858420134),
+        (127, 9223372036854775807, 1143457448, 72172391067272872, 133775006616431480556399798385742694056),
+        (128, 0, 92354039, 6983904075096077815, 142778255028238593264425994737414125047),
+        (128, 42, 2426337586, 12888518128335518002, 143223833101821415339773060354381117746),
+        (128, -1, 103553824, 9346704960620600096, 230069517080850694622503769339094375200),
+        (128, 9223372036854775807, 3957815473, 17034109620113078449, 265811834084111884272243209656575948977),
+        (129, 0, 1714781409, 16993435367043857633, 138968009347081345540070713327804054753),
+        (129, 42, 3161517621, 12935443039076281909, 266822461341380604908068925551418601013),
+        (129, -1, 2464681667, 14077341316147580611, 183838565134203390473413496064228986563),
+        (129, 9223372036854775807, 788471128, 13472440302346116440, 162615251163731526704024711877032353112),
+        (255, 0, 264788521, 6677110828988652073, 30037726632842751415822128637456964137),
+        (255, 42, 3644586444, 9904229635153721804, 214550143375908561272278886853012879820),
+        (255, -1, 827270721, 5977877835796129345, 51126944870946591310077232845631202881),
+        (255, 9223372036854775807, 2174670372, 11870597916720551460, 15527859479946042798290634807957181988),
+        (256, 0, 572563878, 9274147754980975014, 12929408433748801460966499969337958822),
+        (256, 42, 2497026382, 14048247714522962254, 217419737098441994906096601757402896718),
+        (256, -1, 167212926, 7798185451645794174, 122499219359664285552534565418958288766),
+        (256, 9223372036854775807, 3554828308, 10498953851030364180, 165476913285909873039072798505676464148),
+        (257, 0, 1717943005, 10137693446868417245, 18791183980839235348851563178504862429),
+        (257, 42, 786955849, 11334756073201991241, 161381948738761376407625443972418633289),
+        (257, -1, 1045045651, 1179128940825551251, 163446590007871435632079329914646045075),
+        (257, 9223372036854775807, 2289736384, 12707074099453270720, 143766641954683003607205970322107570880),
+        (1000, 0, 3531675279, 1699421015261648527, 280888410490673863607898061042721298063),
+        (1000, 42, 346377900, 12014975010073824940, 231395016056792681974287797418325266092),
+        (1000, -1, 1821812850, 7388093519087708274, 80398013913285019878854686521439987826),
+        (1000, 9223372036854775807, 3857526499, 14824605037940780771, 297175996669473527408188447587407048419),
+        (1024, 0, 3741305613, 6106790925617318669, 114808581071425911858752114347974052621),
+        (1024, 42, 90015525, 440263345117693733, 197030335731461796470995790561305003813),
+        (1024, -1, 114775135, 11960880433620014175, 315574671714532295914932261785014064223),
+        (1024, 9223372036854775807, 1321056519, 14135588574845581575, 25827579405369571399769652659098597639),
+    ];
+
+    fn golden_input() -> [u8; 1024] {
+        core::array::from_fn(|i| (i * 31 + 7) as u8)
+    }
+
+    #[test]
+    fn functions_match_golden_vectors() {
+        let input = golden_input();
+        for &(len, seed, hash32, hash64, hash128) in GOLDEN {
+            let input = &input[..len];
+            assert_eq!(hash32, gxhash32(input, seed), "gxhash32 changed for input of size {len} with seed {seed}");
+            assert_eq!(hash64, gxhash64(input, seed), "gxhash64 changed for input of size {len} with seed {seed}");
+            assert_eq!(hash128, gxhash128(input, seed), "gxhash128 changed for input of size {len} with seed {seed}");
+        }
+    }
+
+    // (input length, seed, finish, finish_u128) of a GxHasher, which differ from the functions of the same seed
+    #[rustfmt::skip]
+    const HASHER_GOLDEN: &[(usize, i64, u64, u128)] = &[
+        (0, 0, 17118817743232439212, 78505093061913940866771591142410949548),
+        (0, 42, 14590886720559638276, 122362309934347153297622924638192133892),
+        (1, 0, 5979665505652935262, 227234859469231243776839597355687316062),
+        (1, 42, 10448273495162113078, 73077820783698618538868203038032809014),
+        (16, 0, 11285876930649782384, 288038920884213812344427279997376919664),
+        (16, 42, 3116225649672279747, 227207404900025898403281732850842162883),
+        (17, 0, 11690094618622262614, 219368255854476753210972718490505411926),
+        (17, 42, 5307859265844302734, 315844872230066898131559836470475379598),
+        (64, 0, 17794409539957823016, 71280894411812782018979913574039819816),
+        (64, 42, 9657191352854589141, 241975294635220423986395616394094784213),
+        (65, 0, 16528395398474933135, 153567218423356909374637692234528236431),
+        (65, 42, 13053289290910078634, 62055923441928642296308752294784734890),
+        (128, 0, 8175479719658509106, 122665254557606160876140703420581925682),
+        (128, 42, 15256407470457570904, 132067798926943078424219803607340238424),
+        (129, 0, 15231711700291409003, 329392047526216143547573240466674013291),
+        (129, 42, 4846216233372604278, 67520258611621963347239924169389365110),
+        (1024, 0, 14775755261920451362, 254330943337565164922334966273793891106),
+        (1024, 42, 6705551280272776839, 118700622798379631210709954372647984775),
+    ];
+
+    #[test]
+    fn hasher_matches_golden_vectors() {
+        let input = golden_input();
+        for &(len, seed, hash64, hash128) in HASHER_GOLDEN {
+            let mut hasher = GxHasher::with_seed(seed);
+            hasher.write(&input[..len]);
+            assert_eq!(hash64, hasher.finish(), "GxHasher changed for input of size {len} with seed {seed}");
+            assert_eq!(hash128, hasher.finish_u128(), "GxHasher changed for input of size {len} with seed {seed}");
+        }
+    }
+
+    fn typed_writes(seed: i64) -> u64 {
+        let mut hasher = GxHasher::with_seed(seed);
+        hasher.write_u8(1);
+        hasher.write_u16(2);
+        hasher.write_u32(3);
+        hasher.write_u64(4);
+        hasher.write_u128(5);
+        hasher.write_usize(6);
+        hasher.write_i64(-7);
+        hasher.write(b"gxhash");
+        hasher.finish()
+    }
+
+    #[test]
+    fn typed_writes_match_golden_vectors() {
+        assert_eq!(4494268105004087905, typed_writes(0));
+        assert_eq!(4124216526397312860, typed_writes(42));
+    }
+
+    #[test]
+    fn hashed_values_match_golden_vectors() {
+        let mut hasher = GxHasher::with_seed(0);
+        "Hello, world!".hash(&mut hasher);
+        assert_eq!(14423007001707686150, hasher.finish());
+        assert_eq!(126110799809290128402363009760434989318, hasher.finish_u128());
+
+        let mut hasher = GxBuildHasher::with_seed(42).build_hasher();
+        "Hello, world!".hash(&mut hasher);
+        assert_eq!(16074985336944202766, hasher.finish());
+        assert_eq!(337419140367847600318084785548122919950, hasher.finish_u128());
+    }
+
+    // Known-answer test vectors published for other implementations, see the header of the file for its format
+    const VECTORS: &str = include_str!("../vectors/v3.txt");
+
+    fn hex(hash: impl Into<u128>, width: usize) -> String {
+        format!("{:0width$x}", hash.into())
+    }
+
+    // gxhash128 on top of the portable platform, whatever the platform of the build
+    fn portable_gxhash128(input: &[u8], seed: i64) -> u128 {
+        use crate::gxhash::portable::*;
+        unsafe { u128::from_le(*(&gxhash(input, create_seed(seed)) as *const State as *const u128)) }
+    }
+
+    // GxHasher on top of the portable platform, whatever the platform of the build
+    struct PortableHasher(crate::gxhash::portable::State);
+
+    impl PortableHasher {
+        fn with_seed(seed: i64) -> PortableHasher {
+            PortableHasher(unsafe { crate::gxhash::portable::create_seed(seed) })
+        }
+
+        fn finish_u128(&self) -> u128 {
+            use crate::gxhash::portable::{finalize, State};
+            unsafe { u128::from_le(*(&finalize(self.0) as *const State as *const u128)) }
+        }
+    }
+
+    macro_rules! portable_write {
+        ($name:ident, $type:ty, $load:ident) => {
+            fn $name(&mut self, value: $type) {
+                use crate::gxhash::portable::{mix, $load};
+                self.0 = unsafe { mix(self.0, $load(value)) };
+            }
+        };
+    }
+
+    impl Hasher for PortableHasher {
+        fn finish(&self) -> u64 {
+            self.finish_u128() as u64
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            use crate::gxhash::portable::{compress_all, mix};
+            self.0 = unsafe { mix(self.0, compress_all(bytes)) };
+        }
+
+        portable_write!(write_u8, u8, load_u8);
+        portable_write!(write_u16, u16, load_u16);
+        portable_write!(write_u32, u32, load_u32);
+        portable_write!(write_u64, u64, load_u64);
+        portable_write!(write_u128, u128, load_u128);
+        portable_write!(write_i8, i8, load_i8);
+        portable_write!(write_i16, i16, load_i16);
+        portable_write!(write_i32, i32, load_i32);
+        portable_write!(write_i64, i64, load_i64);
+        portable_write!(write_i128, i128, load_i128);
+    }
+
+    // Checks the functions and the hasher of this module, of the portable platform and of the reference
+    // implementation, so that the vectors are checked against every implementation on every target
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn matches_published_vectors() {
+        let input = golden_input();
+        let (mut functions, mut writes, mut typed_writes) = (0, 0, 0);
+
+        for line in VECTORS.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let seed: i64 = fields[1].parse().unwrap();
+            let mut hasher = GxHasher::with_seed(seed);
+            let mut portable = PortableHasher::with_seed(seed);
+            let mut reference = crate::reference::GxHasher::with_seed(seed);
+
+            macro_rules! write {
+                ($($name:ident: $type:ty),*) => {
+                    match fields[0] {
+                        "gxhash" => {
+                            let input = &input[..fields[2].parse::<usize>().unwrap()];
+                            assert_eq!(fields[3..], [hex(gxhash32(input, seed), 8), hex(gxhash64(input, seed), 16), hex(gxhash128(input, seed), 32)], "{line}");
+                            let hash = portable_gxhash128(input, seed);
+                            assert_eq!(fields[3..], [hex(hash as u32, 8), hex(hash as u64, 16), hex(hash, 32)], "portable: {line}");
+                            assert_eq!(fields[3..], [
+                                hex(crate::reference::gxhash32(input, seed), 8),
+                                hex(crate::reference::gxhash64(input, seed), 16),
+                                hex(crate::reference::gxhash128(input, seed), 32),
+                            ], "reference: {line}");
+                            functions += 1;
+                            continue;
+                        }
+                        "write" => {
+                            let input = &input[..fields[2].parse::<usize>().unwrap()];
+                            hasher.write(input);
+                            portable.write(input);
+                            reference.write(input);
+                            writes += 1;
+                        }
+                        $(stringify!($name) => {
+                            let value = fields[2].parse::<$type>().unwrap();
+                            hasher.$name(value);
+                            portable.$name(value);
+                            reference.$name(value);
+                            typed_writes += 1;
+                        })*
+                        kind => panic!("unknown vector kind {kind}"),
+                    }
+                };
+            }
+            write!(write_u8: u8, write_u16: u16, write_u32: u32, write_u64: u64, write_u128: u128,
+                write_i8: i8, write_i16: i16, write_i32: i32, write_i64: i64, write_i128: i128);
+
+            assert_eq!(fields[3..], [hex(hasher.finish(), 16), hex(hasher.finish_u128(), 32)], "{line}");
+            assert_eq!(fields[3..], [hex(portable.finish(), 16), hex(portable.finish_u128(), 32)], "portable: {line}");
+            assert_eq!(fields[3..], [hex(reference.finish(), 16), hex(reference.finish_u128(), 32)], "reference: {line}");
+        }
+
+        // Every length up to 1024 for 4 seeds, so that a truncated file doesn't go unnoticed
+        assert_eq!(4 * 1025, functions);
+        assert_eq!(4 * 129, writes);
+        assert_eq!(4 * (5 * 5 + 5 * 6), typed_writes);
+    }
+}